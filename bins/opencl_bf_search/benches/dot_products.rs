@@ -1,49 +1,174 @@
 use criterion::Criterion;
 use criterion::{criterion_group, criterion_main};
 use criterion::{BenchmarkId, Throughput};
-use memchunk::AnySizeMemoryChunk;
+use memchunk::{
+    compute_metric, AccessHint, AnySizeMemoryChunk, BlockedDotProduct, DotProduct, Metric,
+    ReferenceDotProduct, ReferenceDotProductParallel, ReferenceDotProductUnrolled,
+};
 use std::hint::black_box;
 use std::path::PathBuf;
 use vecdb::VecDb;
 
+const SIZES: [usize; 3] = [1024, 2048, 131_072];
+
 fn from_elem(c: &mut Criterion) {
     let rt = tokio::runtime::Runtime::new().unwrap();
     let mut chunk = rt.block_on(async { load_vectors(131_072).await });
 
     let first_vec = Vec::from(chunk.get_vec(0));
-    let sizes = [1024usize, 2048, 131_072];
 
-    let mut group = c.benchmark_group("search_naive");
-    for size in sizes.iter() {
-        group.throughput(Throughput::Elements(*size as u64));
-        chunk.use_num_vecs((*size).into());
-        group.bench_function(BenchmarkId::from_parameter(size), |b| {
-            b.iter(|| chunk.search_naive(black_box(&first_vec)));
-        });
-    }
-    group.finish();
+    run_bench_group(
+        c,
+        "reference",
+        &mut chunk,
+        &first_vec,
+        |chunk, query, results| dot_product(&ReferenceDotProduct::default(), chunk, query, results),
+    );
 
-    let mut group = c.benchmark_group("search_unrolled::<8>");
-    for size in sizes.iter() {
-        group.throughput(Throughput::Elements(*size as u64));
-        chunk.use_num_vecs((*size).into());
-        group.bench_function(BenchmarkId::from_parameter(size), |b| {
-            b.iter(|| chunk.search_unrolled::<8>(black_box(&first_vec)));
+    run_bench_group(
+        c,
+        "reference_unrolled::<8>",
+        &mut chunk,
+        &first_vec,
+        |chunk, query, results| {
+            dot_product(
+                &ReferenceDotProductUnrolled::<8>::default(),
+                chunk,
+                query,
+                results,
+            )
+        },
+    );
+
+    run_bench_group(
+        c,
+        "reference_unrolled::<16>",
+        &mut chunk,
+        &first_vec,
+        |chunk, query, results| {
+            dot_product(
+                &ReferenceDotProductUnrolled::<16>::default(),
+                chunk,
+                query,
+                results,
+            )
+        },
+    );
+
+    run_bench_group(
+        c,
+        "reference_parallel",
+        &mut chunk,
+        &first_vec,
+        |chunk, query, results| {
+            dot_product(
+                &ReferenceDotProductParallel::default(),
+                chunk,
+                query,
+                results,
+            )
+        },
+    );
+
+    let mut huge_chunk = AnySizeMemoryChunk::builder()
+        .num_vectors(chunk.num_vecs())
+        .num_dimensions(chunk.num_dims())
+        .access_hint(AccessHint::Seqential)
+        .huge_pages(true)
+        .build()
+        .unwrap();
+    huge_chunk.as_mut_slice().copy_from_slice(chunk.as_slice());
+    run_bench_group(
+        c,
+        "reference_huge_pages",
+        &mut huge_chunk,
+        &first_vec,
+        |chunk, query, results| dot_product(&ReferenceDotProduct::default(), chunk, query, results),
+    );
+    drop(huge_chunk);
+
+    run_bench_group(
+        c,
+        "blocked",
+        &mut chunk,
+        &first_vec,
+        |chunk, query, results| dot_product(&BlockedDotProduct::default(), chunk, query, results),
+    );
+
+    #[cfg(all(feature = "avx512", target_arch = "x86_64"))]
+    run_bench_group(
+        c,
+        "avx512",
+        &mut chunk,
+        &first_vec,
+        |chunk, query, results| {
+            dot_product(
+                &memchunk::Avx512DotProduct::default(),
+                chunk,
+                query,
+                results,
+            )
+        },
+    );
+
+    for (name, metric, parallel) in [
+        ("cosine_similarity", Metric::Cosine, false),
+        ("cosine_similarity_parallel", Metric::Cosine, true),
+        ("euclidean_distance_sq", Metric::EuclideanSq, false),
+        ("euclidean_distance_sq_parallel", Metric::EuclideanSq, true),
+    ] {
+        run_bench_group(c, name, &mut chunk, &first_vec, |chunk, query, results| {
+            compute_metric(
+                metric,
+                query,
+                chunk.as_slice(),
+                chunk.num_dims(),
+                results.len().into(),
+                results,
+                parallel,
+            )
         });
     }
-    group.finish();
+}
 
-    let mut group = c.benchmark_group("search_unrolled::<16>");
-    for size in sizes.iter() {
-        group.throughput(Throughput::Elements(*size as u64));
-        chunk.use_num_vecs((*size).into());
+/// Benchmarks the scoring function over the first vectors of the chunk for each of the [`SIZES`].
+fn run_bench_group<F>(
+    c: &mut Criterion,
+    name: &str,
+    chunk: &mut AnySizeMemoryChunk,
+    query: &[f32],
+    score: F,
+) where
+    F: Fn(&AnySizeMemoryChunk, &[f32], &mut [f32]),
+{
+    let mut group = c.benchmark_group(name);
+    for size in SIZES {
+        group.throughput(Throughput::Elements(size as u64));
+        chunk.use_num_vecs(size.into());
+        let mut results = vec![0.0; *chunk.num_vecs()];
+        let chunk = &*chunk;
         group.bench_function(BenchmarkId::from_parameter(size), |b| {
-            b.iter(|| chunk.search_unrolled::<16>(black_box(&first_vec)));
+            b.iter(|| score(chunk, black_box(query), black_box(&mut results)));
         });
     }
     group.finish();
 }
 
+fn dot_product<D: DotProduct>(
+    dot_product: &D,
+    chunk: &AnySizeMemoryChunk,
+    query: &[f32],
+    results: &mut [f32],
+) {
+    dot_product.dot_product(
+        query,
+        chunk.as_slice(),
+        chunk.num_dims(),
+        results.len().into(),
+        results,
+    );
+}
+
 async fn load_vectors(sample_size: usize) -> AnySizeMemoryChunk {
     let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
         .join("..")
@@ -51,31 +176,10 @@ async fn load_vectors(sample_size: usize) -> AnySizeMemoryChunk {
         .join("vectors.bin");
     let mut db = VecDb::open_read(path).await.unwrap();
 
-    let num_vecs = *db.num_vectors;
-    let num_dims = *db.num_dimensions;
-
-    let sample_size = (if sample_size > 0 {
-        num_vecs.min(sample_size)
-    } else {
-        num_vecs
-    })
-    .into();
-
-    let mut chunk = AnySizeMemoryChunk::new(sample_size, db.num_dimensions);
-    let data = chunk.as_mut();
-
     println!("Loading {sample_size} elements from vector database ...");
-    let num_read = db
-        .read_n_vecs(sample_size, |v, vec| {
-            let start = v * num_dims;
-            let end = start + num_dims;
-            data[start..end].copy_from_slice(vec);
-            true
-        })
+    db.load_chunk(sample_size.into(), AccessHint::Seqential)
         .await
-        .unwrap();
-    assert_eq!(num_read, *sample_size);
-    chunk
+        .unwrap()
 }
 
 criterion_group!(benches, from_elem);
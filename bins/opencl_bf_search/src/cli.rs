@@ -1,4 +1,6 @@
+use clap::builder::{ArgPredicate, PossibleValuesParser, TypedValueParser};
 use clap::{Arg, ArgAction, ArgMatches, Command, ValueHint};
+use memchunk::Metric;
 use std::path::PathBuf;
 
 pub fn match_cli_arguments() -> ArgMatches {
@@ -30,12 +32,62 @@ pub fn match_cli_arguments() -> ArgMatches {
                 .short('d')
                 .long("device")
                 .value_name("DEVICE_ID")
-                .help("The ID of the selected platform's device to use")
+                .help("The ID of the selected platform's device to use; repeat to split the work across devices")
                 .help_heading("OpenCL")
                 .num_args(1)
+                .action(ArgAction::Append)
                 .allow_negative_numbers(false)
                 .value_parser(ocl_device_valid),
         )
+        .arg(
+            Arg::new("ocl-fp64")
+                .long("fp64")
+                .help("Additionally runs the dot products in double precision to compare accuracy")
+                .help_heading("OpenCL")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("ocl-local-x")
+                .long("local-x")
+                .value_name("ROWS")
+                .help("The number of rows processed by a work group of the dot product kernels")
+                .help_heading("OpenCL")
+                .default_value("16")
+                .num_args(1)
+                .allow_negative_numbers(false)
+                .value_parser(power_of_two),
+        )
+        .arg(
+            Arg::new("ocl-local-p")
+                .long("local-p")
+                .value_name("PARTIALS")
+                .help("The number of partial sums per row computed by a work group of the dot product kernels")
+                .help_heading("OpenCL")
+                .default_value("16")
+                .num_args(1)
+                .allow_negative_numbers(false)
+                .value_parser(power_of_two),
+        )
+        .arg(
+            Arg::new("ocl-validate")
+                .long("validate")
+                .help("Compares the OpenCL dot products against the CPU reference and fails if they deviate")
+                .help_heading("OpenCL")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("ocl-metric")
+                .long("metric")
+                .value_name("METRIC")
+                .help("Additionally runs the kernel for the specified metric; `dot` only runs the dot product benchmark")
+                .help_heading("OpenCL")
+                .num_args(1)
+                .default_value("dot")
+                .value_parser(
+                    PossibleValuesParser::new(Metric::NAMES)
+                        .map(|s| s.parse::<Metric>().expect("invalid metric")),
+                ),
+        )
         .arg(
             Arg::new("vector-db")
                 .short('i')
@@ -45,6 +97,7 @@ pub fn match_cli_arguments() -> ArgMatches {
                 .help("The vector database to load")
                 .default_value("vectors.bin")
                 .default_value_if("ocl-list-platforms", "true", None)
+                .default_value_if("synthetic", ArgPredicate::IsPresent, None)
                 .num_args(1)
                 .value_parser(file_valid)
                 .help_heading("Vector Database"),
@@ -60,6 +113,35 @@ pub fn match_cli_arguments() -> ArgMatches {
                 .allow_negative_numbers(false)
                 .value_parser(num_vecs)
                 .help_heading("Vector Database"),
+        )
+        .arg(
+            Arg::new("synthetic")
+                .long("synthetic")
+                .value_name("COUNT")
+                .help("Generates the specified number of random unit vectors instead of loading the vector database")
+                .num_args(1)
+                .allow_negative_numbers(false)
+                .value_parser(synthetic_num_vecs)
+                .help_heading("Vector Database"),
+        )
+        .arg(
+            Arg::new("synthetic-dims")
+                .long("dims")
+                .value_name("COUNT")
+                .help("The number of dimensions of the generated vectors")
+                .default_value("384")
+                .requires("synthetic")
+                .num_args(1)
+                .allow_negative_numbers(false)
+                .value_parser(num_dims)
+                .help_heading("Vector Database"),
+        )
+        .arg(
+            Arg::new("normalize-query")
+                .long("normalize-query")
+                .help("Normalizes the query vector to unit length and checks that the data vectors are unit-length, so that dot products equal cosine similarities")
+                .help_heading("Vector Database")
+                .action(ArgAction::SetTrue),
         );
 
     command.get_matches()
@@ -135,3 +217,28 @@ fn num_vecs(s: &str) -> Result<usize, String> {
         Ok(count)
     }
 }
+
+fn synthetic_num_vecs(s: &str) -> Result<usize, String> {
+    match num_vecs(s)? {
+        0 => Err(String::from("The number must be positive")),
+        count => Ok(count),
+    }
+}
+
+fn num_dims(s: &str) -> Result<usize, String> {
+    let count: usize = s.parse().map_err(|e| format!("{e}"))?;
+    if count == 0 {
+        Err(String::from("The number must be positive"))
+    } else {
+        Ok(count)
+    }
+}
+
+fn power_of_two(s: &str) -> Result<usize, String> {
+    let value: usize = s.parse().map_err(|e| format!("{e}"))?;
+    if value.is_power_of_two() {
+        Ok(value)
+    } else {
+        Err(String::from("The number must be a power of two"))
+    }
+}
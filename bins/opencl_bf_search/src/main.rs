@@ -1,45 +1,110 @@
 mod cli;
+mod multi_device;
 mod opencl;
+mod query_stream;
 mod vec_traits;
 mod vecgen;
 
 use crate::cli::match_cli_arguments;
+use crate::multi_device::dot_product_multi_device;
 use crate::opencl::{
-    build_dot_product_program, get_opencl_selection, ocl_print_platforms, OpenClDeviceSelection,
+    build_distance_program, build_dot_product_program, build_dot_product_program_f64,
+    device_supports_fp64, get_opencl_selection, kernel_name, ocl_print_platforms,
+    OpenClDeviceSelection, TopKReduction, WorkGroupSize,
 };
-use memchunk::{AnySizeMemoryChunk, DotProduct, ReferenceDotProductParallel};
-use ocl::{Buffer, Context, Kernel, MemFlags, Queue};
+use crate::query_stream::QueryStream;
+use crate::vec_traits::{CosineSimilarity, L1Distance, L2Norm, Normalize};
+use crate::vecgen::Vecgen;
+use clap::ArgMatches;
+use memchunk::{
+    compute_metric, detected_features, rmse, topk, AccessHint, AnySizeMemoryChunk, DotProduct,
+    Metric, ReferenceDotProductParallel,
+};
+use ocl::{Buffer, Context, Device, Kernel, MemFlags, Platform, Queue};
 use std::path::PathBuf;
 use std::time::Instant;
 use vecdb::VecDb;
 
+/// The exit code of `--list-platforms` if no OpenCL platform is available.
+const EXIT_NO_OPENCL_PLATFORMS: i32 = 2;
+
+/// The exit code of `--validate` if the OpenCL results deviate from the CPU reference.
+const EXIT_VALIDATION_FAILED: i32 = 3;
+
+/// The maximum root-mean-square error between the OpenCL results and the CPU reference
+/// accepted by `--validate`.
+const VALIDATION_THRESHOLD: f32 = 1e-3;
+
+/// The number of best matches selected by the top-K reduction kernel.
+const TOP_K: usize = 16;
+
+/// The exit code if the selected device does not support the requested work group size
+/// or the vectors do not fill a single work group.
+const EXIT_INVALID_WORK_GROUP_SIZE: i32 = 4;
+
+/// The number of vectors in use is truncated to a multiple of this value, or of the
+/// number of rows of a work group if that is larger; both are powers of two.
+const VECTOR_ALIGNMENT: usize = 32;
+
 #[tokio::main]
 async fn main() {
     let matches = match_cli_arguments();
 
     if matches.get_flag("ocl-list-platforms") {
-        ocl_print_platforms();
-        std::process::exit(0);
+        let exit_code = if ocl_print_platforms() {
+            0
+        } else {
+            EXIT_NO_OPENCL_PLATFORMS
+        };
+        std::process::exit(exit_code);
     }
 
-    let db_file = matches
-        .get_one("vector-db")
-        .expect("input argument missing");
-
-    let num_vecs = matches
-        .get_one::<usize>("max-vectors")
-        .expect("invalid number of vectors")
-        .to_owned();
+    println!("Detected CPU features: {}", detected_features().join(", "));
 
     let opencl_selection = get_opencl_selection(&matches);
 
-    let mut chunk = load_vectors(db_file, num_vecs).await;
-    let first_vec = Vec::from(chunk.get_vec(0));
+    let mut chunk = if let Some(&num_vecs) = matches.get_one::<usize>("synthetic") {
+        let num_dims = matches
+            .get_one::<usize>("synthetic-dims")
+            .expect("invalid number of dimensions")
+            .to_owned();
+        generate_vectors(num_vecs, num_dims)
+    } else {
+        let db_file = matches
+            .get_one("vector-db")
+            .expect("input argument missing");
+
+        let num_vecs = matches
+            .get_one::<usize>("max-vectors")
+            .expect("invalid number of vectors")
+            .to_owned();
+
+        load_vectors(db_file, num_vecs).await
+    };
+    let mut first_vec = Vec::from(chunk.get_vec(0));
+
+    // The kernels compute raw dot products, which only equal the cosine similarity for unit vectors.
+    if matches.get_flag("normalize-query") {
+        first_vec.normalize_in_place();
+
+        let num_denormal = count_denormal_vectors(&chunk);
+        if num_denormal > 0 {
+            println!(
+                "Warning: {num_denormal} data vectors are not unit-length; scores are not cosine similarities"
+            );
+        }
+    }
 
     chunk.double();
 
-    // HACK: Ensure number of vectors is multiple of 32.
-    chunk.use_num_vecs((chunk.num_vecs().into_inner() & !(32 - 1)).into());
+    let work_group = get_work_group_size(&matches);
+    if let Err(e) = work_group.validate_num_vecs(*chunk.num_vecs(), VECTOR_ALIGNMENT) {
+        eprintln!("{e}");
+        std::process::exit(EXIT_INVALID_WORK_GROUP_SIZE);
+    }
+
+    // The dot product kernels process the vectors in work groups of a fixed number of rows.
+    chunk.truncate_to_multiple_of(VECTOR_ALIGNMENT.max(work_group.rows));
     println!("Using {} vectors.", chunk.num_vecs());
 
     let reference_algo = ReferenceDotProductParallel::default();
@@ -60,19 +125,25 @@ async fn main() {
         duration = duration_cpu
     );
 
-    println!("{:?} ...", &reference[..10]);
+    println!("{:?} ...", preview(&reference, 0));
     println!(
         "{:?} ...",
-        &reference[chunk.num_dims().into_inner()..(chunk.num_dims().into_inner() + 10)]
+        preview(&reference, chunk.num_dims().into_inner())
     );
 
-    if opencl_selection.is_none() {
+    let Some(OpenClDeviceSelection {
+        platform,
+        device,
+        devices,
+    }) = opencl_selection
+    else {
+        if Platform::list().is_empty() {
+            println!("No OpenCL platform available; ran CPU-only");
+        } else {
+            println!("No OpenCL device selected; ran CPU-only");
+        }
         return;
-    }
-
-    let OpenClDeviceSelection {
-        platform, device, ..
-    } = opencl_selection.expect("invalid selection");
+    };
 
     // Default setup.
     println!(
@@ -83,6 +154,13 @@ async fn main() {
 
     println!("Using device {}", device.name().unwrap());
 
+    for device in &devices {
+        if let Err(e) = work_group.validate(device) {
+            eprintln!("{e}");
+            std::process::exit(EXIT_INVALID_WORK_GROUP_SIZE);
+        }
+    }
+
     let context = Context::builder()
         .platform(platform)
         .devices(device)
@@ -95,7 +173,6 @@ async fn main() {
     let matrix_queue = Queue::new(&context, device, None).unwrap();
     let vector_queue = Queue::new(&context, device, None).unwrap();
     let result_queue = Queue::new(&context, device, None).unwrap();
-    // TODO: Introduce another queue for reducing the results?
 
     // Write matrix data to the device using matrix_queue.
     let matrix_buffer = Buffer::<f32>::builder()
@@ -122,26 +199,23 @@ async fn main() {
         .unwrap();
 
     // Execute kernel using result_queue.
-    const X: usize = 16;
-    const P: usize = 16;
-
     let dot_product_kernel = Kernel::builder()
         .program(&dot_product)
         .name("dot_product")
         .queue(result_queue.clone())
-        .global_work_size([chunk.num_vecs().into_inner(), P])
-        .local_work_size([X, P])
+        .global_work_size(work_group.global_work_size(chunk.num_vecs().into_inner()))
+        .local_work_size(work_group.local_work_size())
         .arg(&matrix_buffer)
         .arg(&vector_buffer)
         .arg(&result_buffer)
-        .arg_local::<f32>(X * (P + 1))
+        .arg_local::<f32>(work_group.local_mem_len())
         .arg(chunk.num_vecs().into_inner() as u32)
         .arg(chunk.num_dims().into_inner() as u32)
         .build()
         .unwrap();
 
     println!("Transposing matrix ...");
-    let transposed = chunk.as_transposed();
+    let transposed = chunk.as_transposed_parallel();
 
     println!("Processing using OpenCL ...");
     let start = Instant::now();
@@ -175,9 +249,6 @@ async fn main() {
     // Flush result_queue to make sure that the read operation has been sent to the device.
     result_queue.flush().unwrap();
 
-    // TODO: Write next matrix ...
-    // TODO: Write next vector ...
-
     // Just to ensure we have everything set here in the single-matrix example, we now
     // block on the result queue to make sure that the read operation has completed
     result_queue.finish().unwrap();
@@ -193,49 +264,412 @@ async fn main() {
         ratio_kernel = duration_cpu / duration_ocl_kernel,
     );
 
-    println!("{:?} ...", &results[..10]);
+    println!("{:?} ...", preview(&results, 0));
+    println!("{:?} ...", preview(&results, chunk.num_dims().into_inner()));
+
+    if matches.get_flag("ocl-validate") && !validate_results(&reference, &results) {
+        std::process::exit(EXIT_VALIDATION_FAILED);
+    }
+
+    // Select the best matches on the device, so that only few candidates are read back.
+    // Running the reduction on the result queue orders it after the dot product kernel.
+    let topk_reduction =
+        TopKReduction::<TOP_K>::new(&device, &context, &result_queue, &result_buffer).unwrap();
+    let start = Instant::now();
+    let topk_ocl = topk_reduction.run().unwrap();
+    let duration_topk = (Instant::now() - start).as_secs_f32();
+    let topk_cpu = topk::<TOP_K, f32>(&mut reference.clone());
+    println!("Duration selecting the top {TOP_K} results in OpenCL: {duration_topk} s");
     println!(
-        "{:?} ...",
-        &results[chunk.num_dims().into_inner()..(chunk.num_dims().into_inner() + 10)]
+        "Top {TOP_K} indices (OpenCL): {:?}",
+        topk_ocl.map(|entry| entry.index())
+    );
+    println!(
+        "Top {TOP_K} indices (CPU):    {:?}",
+        topk_cpu.map(|entry| entry.index())
+    );
+
+    if let Some(best) = topk_cpu
+        .iter()
+        .max_by(|lhs, rhs| lhs.value().total_cmp(&rhs.value()))
+    {
+        let best_vec = chunk.get_vec(best.index());
+        println!(
+            "Best match {index}: cosine similarity {similarity}, L1 distance {distance}",
+            index = best.index(),
+            similarity = first_vec.cosine_similarity(best_vec),
+            distance = first_vec.l1_distance(best_vec),
+        );
+    }
+
+    // Process a stream of queries, overlapping the query uploads with the kernel executions.
+    const NUM_QUERIES: usize = 16;
+    let query_stream = QueryStream::new(
+        &context,
+        device,
+        &dot_product,
+        &transposed,
+        chunk.num_vecs().into_inner(),
+        chunk.num_dims().into_inner(),
+        work_group,
+    )
+    .unwrap();
+
+    println!("Processing {NUM_QUERIES} queries using OpenCL ...");
+    let start = Instant::now();
+    // The first query is the (possibly normalized) query processed above.
+    let queries = std::iter::once(first_vec.as_slice()).chain(chunk.rows().skip(1));
+    let stream_results = query_stream.process(queries.take(NUM_QUERIES)).unwrap();
+    let duration = (Instant::now() - start).as_secs_f32();
+    println!(
+        "Duration processing {NUM_QUERIES} queries against {vecs} vectors in OpenCL: {duration} s ({per_query} s per query)",
+        vecs = chunk.num_vecs(),
+        per_query = duration / NUM_QUERIES as f32
     );
+    debug_assert_eq!(stream_results[0], results);
+
+    if matches.get_flag("ocl-fp64") {
+        process_fp64(
+            &context,
+            device,
+            &chunk,
+            &transposed,
+            &first_vec,
+            &results,
+            work_group,
+        );
+    }
+
+    let metric = matches
+        .get_one::<Metric>("ocl-metric")
+        .copied()
+        .unwrap_or(Metric::Dot);
+    if metric != Metric::Dot {
+        process_metric(
+            &context,
+            device,
+            &chunk,
+            &transposed,
+            &first_vec,
+            metric,
+            work_group,
+        );
+    }
+
+    if devices.len() > 1 {
+        println!("Processing using {} OpenCL devices ...", devices.len());
+        let start = Instant::now();
+        let results = dot_product_multi_device(
+            platform,
+            &devices,
+            &transposed,
+            &first_vec,
+            chunk.num_vecs().into_inner(),
+            chunk.num_dims().into_inner(),
+            work_group,
+        );
+        let duration = (Instant::now() - start).as_secs_f32();
+        println!(
+            "Duration processing {vecs} vectors on {count} OpenCL devices (full roundtrip): {duration} s (x{ratio})",
+            vecs = chunk.num_vecs(),
+            count = devices.len(),
+            ratio = duration_cpu / duration,
+        );
+        println!("{:?} ...", preview(&results, 0));
+    }
 }
 
-async fn load_vectors(db_file: &PathBuf, sample_size: usize) -> AnySizeMemoryChunk {
-    let mut db = VecDb::open_read(db_file).await.unwrap();
+/// Repeats the OpenCL dot products in double precision and reports the error of
+/// the single- and double-precision results against a double-precision CPU reference.
+fn process_fp64(
+    context: &Context,
+    device: Device,
+    chunk: &AnySizeMemoryChunk,
+    transposed: &[f32],
+    query: &[f32],
+    results_f32: &[f32],
+    work_group: WorkGroupSize,
+) {
+    if !device_supports_fp64(&device) {
+        eprintln!("The selected device does not support double precision (cl_khr_fp64)");
+        return;
+    }
+
+    let num_vecs = chunk.num_vecs().into_inner();
+    let num_dims = chunk.num_dims().into_inner();
 
+    let reference: Vec<f64> = chunk
+        .rows()
+        .map(|row| {
+            row.iter()
+                .zip(query)
+                .map(|(&x, &y)| x as f64 * y as f64)
+                .sum()
+        })
+        .collect();
+
+    let dot_product = build_dot_product_program_f64(device, context).unwrap();
+    let queue = Queue::new(context, device, None).unwrap();
+
+    let matrix: Vec<f64> = transposed.iter().map(|&x| x as f64).collect();
+    let vector: Vec<f64> = query.iter().map(|&x| x as f64).collect();
+
+    let matrix_buffer = Buffer::<f64>::builder()
+        .queue(queue.clone())
+        .flags(MemFlags::new().read_only().host_write_only())
+        .len(num_vecs * num_dims)
+        .build()
+        .unwrap();
+
+    let vector_buffer = Buffer::<f64>::builder()
+        .queue(queue.clone())
+        .flags(MemFlags::new().read_only().host_write_only())
+        .len(num_dims)
+        .build()
+        .unwrap();
+
+    let result_buffer = Buffer::<f64>::builder()
+        .queue(queue.clone())
+        .flags(MemFlags::new().write_only().host_read_only())
+        .len(num_vecs)
+        .build()
+        .unwrap();
+
+    let dot_product_kernel = Kernel::builder()
+        .program(&dot_product)
+        .name("dot_product_f64")
+        .queue(queue.clone())
+        .global_work_size(work_group.global_work_size(num_vecs))
+        .local_work_size(work_group.local_work_size())
+        .arg(&matrix_buffer)
+        .arg(&vector_buffer)
+        .arg(&result_buffer)
+        .arg_local::<f64>(work_group.local_mem_len())
+        .arg(num_vecs as u32)
+        .arg(num_dims as u32)
+        .build()
+        .unwrap();
+
+    println!("Processing using OpenCL in double precision ...");
     let start = Instant::now();
 
-    let num_vecs = *db.num_vectors;
-    let num_dims = *db.num_dimensions;
+    matrix_buffer.cmd().write(&matrix).enq().unwrap();
+    vector_buffer.cmd().write(&vector).enq().unwrap();
+    unsafe { dot_product_kernel.cmd().enq().unwrap() };
 
-    let sample_size = (if sample_size > 0 {
-        num_vecs.min(sample_size)
-    } else {
-        num_vecs
-    })
-    .into();
-
-    let mut chunk = AnySizeMemoryChunk::new(sample_size, db.num_dimensions);
-    let data = chunk.as_mut();
-
-    println!("Loading {sample_size} elements from vector database ...");
-    let num_read = db
-        .read_n_vecs(sample_size, |v, vec| {
-            debug_assert_eq!(vec.len(), num_dims);
-            #[cfg(debug_assertions)]
-            {
-                let norm = vec.iter().fold(0.0f32, |prev, x| prev + x * x).sqrt();
-                debug_assert!((norm - 1.0f32).abs() < 0.001f32, "Denormal vector detected");
+    let mut results = vec![f64::NAN; num_vecs];
+    result_buffer.cmd().read(&mut results).enq().unwrap();
+    queue.finish().unwrap();
+
+    let duration = (Instant::now() - start).as_secs_f32();
+    println!(
+        "Duration processing {num_vecs} vectors in OpenCL (f64, full roundtrip): {duration} s"
+    );
+
+    println!(
+        "Maximum absolute error against the f64 reference: f32 {error_f32:e}, f64 {error_f64:e}",
+        error_f32 = max_abs_error(results_f32.iter().map(|&x| x as f64), &reference),
+        error_f64 = max_abs_error(results.iter().copied(), &reference),
+    );
+}
+
+/// Runs the kernel for the metric and reports the error against a CPU reference.
+fn process_metric(
+    context: &Context,
+    device: Device,
+    chunk: &AnySizeMemoryChunk,
+    transposed: &[f32],
+    query: &[f32],
+    metric: Metric,
+    work_group: WorkGroupSize,
+) {
+    let num_vecs = chunk.num_vecs().into_inner();
+    let num_dims = chunk.num_dims().into_inner();
+
+    let mut reference = vec![0.0; num_vecs];
+    compute_metric(
+        metric,
+        query,
+        chunk.as_slice(),
+        chunk.num_dims(),
+        chunk.num_vecs(),
+        &mut reference,
+        true,
+    );
+    let reference: Vec<f64> = reference.iter().map(|&x| x as f64).collect();
+
+    let program = build_distance_program(device, context, metric).unwrap();
+    let queue = Queue::new(context, device, None).unwrap();
+
+    let matrix_buffer = Buffer::<f32>::builder()
+        .queue(queue.clone())
+        .flags(MemFlags::new().read_only().host_write_only())
+        .len(num_vecs * num_dims)
+        .build()
+        .unwrap();
+
+    let vector_buffer = Buffer::<f32>::builder()
+        .queue(queue.clone())
+        .flags(MemFlags::new().read_only().host_write_only())
+        .len(num_dims)
+        .build()
+        .unwrap();
+
+    let result_buffer = Buffer::<f32>::builder()
+        .queue(queue.clone())
+        .flags(MemFlags::new().write_only().host_read_only())
+        .len(num_vecs)
+        .build()
+        .unwrap();
+
+    // The cosine kernel divides by the precomputed norms of the data vectors and the query.
+    let norms: Vec<f32> = chunk.rows().map(|row| row.l2_norm_sq().sqrt()).collect();
+    let norm_buffer = Buffer::<f32>::builder()
+        .queue(queue.clone())
+        .flags(MemFlags::new().read_only().host_write_only())
+        .len(num_vecs)
+        .build()
+        .unwrap();
+
+    let mut kernel_builder = Kernel::builder();
+    kernel_builder
+        .program(&program)
+        .name(kernel_name(metric))
+        .queue(queue.clone())
+        .global_work_size(work_group.global_work_size(num_vecs))
+        .local_work_size(work_group.local_work_size())
+        .arg(&matrix_buffer)
+        .arg(&vector_buffer)
+        .arg(&result_buffer)
+        .arg_local::<f32>(work_group.local_mem_len())
+        .arg(num_vecs as u32)
+        .arg(num_dims as u32);
+    if metric == Metric::Cosine {
+        kernel_builder
+            .arg(&norm_buffer)
+            .arg(query.l2_norm_sq().sqrt());
+    }
+    let kernel = kernel_builder.build().unwrap();
+
+    println!("Processing {metric:?} using OpenCL ...");
+    let start = Instant::now();
+
+    matrix_buffer.cmd().write(transposed).enq().unwrap();
+    vector_buffer.cmd().write(query).enq().unwrap();
+    if metric == Metric::Cosine {
+        norm_buffer.cmd().write(&norms).enq().unwrap();
+    }
+    unsafe { kernel.cmd().enq().unwrap() };
+
+    let mut results = vec![f32::NAN; num_vecs];
+    result_buffer.cmd().read(&mut results).enq().unwrap();
+    queue.finish().unwrap();
+
+    let duration = (Instant::now() - start).as_secs_f32();
+    println!(
+        "Duration processing {num_vecs} vectors in OpenCL ({metric:?}, full roundtrip): {duration} s"
+    );
+    println!("{:?} ...", preview(&results, 0));
+    println!(
+        "Maximum absolute error against the CPU reference: {error:e}",
+        error = max_abs_error(results.iter().map(|&x| x as f64), &reference),
+    );
+}
+
+/// Selects up to ten values starting at `start` for printing, or none if `start` is out of bounds.
+fn preview(values: &[f32], start: usize) -> &[f32] {
+    let start = start.min(values.len());
+    &values[start..(start + 10).min(values.len())]
+}
+
+/// Reads the local work size of the dot product kernels from the command line.
+fn get_work_group_size(matches: &ArgMatches) -> WorkGroupSize {
+    let default = WorkGroupSize::default();
+    WorkGroupSize {
+        rows: matches
+            .get_one::<usize>("ocl-local-x")
+            .copied()
+            .unwrap_or(default.rows),
+        partials: matches
+            .get_one::<usize>("ocl-local-p")
+            .copied()
+            .unwrap_or(default.partials),
+    }
+}
+
+/// Compares the OpenCL results against the CPU reference and reports the deviation.
+/// Returns `false` if the root-mean-square error exceeds the [`VALIDATION_THRESHOLD`].
+fn validate_results(reference: &[f32], results: &[f32]) -> bool {
+    let rmse = rmse(reference, results);
+    let (index, error) = reference
+        .iter()
+        .zip(results)
+        .map(|(x, y)| (x - y).abs())
+        .enumerate()
+        .fold((0, 0.0f32), |max, (i, error)| {
+            // NaN errors count as the maximum error, and the first one is kept.
+            if max.1.is_nan() || error <= max.1 {
+                max
+            } else {
+                (i, error)
             }
+        });
 
-            let start = v * num_dims;
-            let end = start + num_dims;
-            data[start..end].copy_from_slice(vec);
+    let passed = rmse <= VALIDATION_THRESHOLD;
+    println!(
+        "Validation {outcome}: RMSE {rmse} (threshold {VALIDATION_THRESHOLD}), max. absolute error {error} at index {index}",
+        outcome = if passed { "passed" } else { "failed" }
+    );
+    passed
+}
 
-            true
-        })
+/// Calculates the root-mean-square error between two equally sized slices.
+fn max_abs_error<I: Iterator<Item = f64>>(results: I, reference: &[f64]) -> f64 {
+    results
+        .zip(reference)
+        .map(|(x, &y)| (x - y).abs())
+        .fold(0.0, f64::max)
+}
+
+/// Counts the vectors of the chunk whose L2 norm deviates from one.
+fn count_denormal_vectors(chunk: &AnySizeMemoryChunk) -> usize {
+    chunk
+        .rows()
+        .filter(|vec| (vec.l2_norm() - 1.0f32).abs() >= 0.001f32)
+        .count()
+}
+
+/// Generates random unit vectors in place of a vector database.
+fn generate_vectors(num_vecs: usize, num_dims: usize) -> AnySizeMemoryChunk {
+    let start = Instant::now();
+
+    println!("Generating {num_vecs} random vectors with {num_dims} dimensions ...");
+    let mut chunk = AnySizeMemoryChunk::new(num_vecs.into(), num_dims.into());
+    Vecgen::new_from_entropy().fill_normalized(chunk.as_mut_slice(), num_dims);
+
+    let duration = Instant::now() - start;
+    println!("Generating duration {} s", duration.as_secs_f32());
+
+    chunk
+}
+
+async fn load_vectors(db_file: &PathBuf, sample_size: usize) -> AnySizeMemoryChunk {
+    let mut db = VecDb::open_read(db_file).await.unwrap();
+
+    let start = Instant::now();
+
+    println!("Loading vectors from vector database ...");
+    let chunk = db
+        .load_chunk(sample_size.into(), AccessHint::Seqential)
         .await
         .unwrap();
+    let num_read = *chunk.num_vecs();
+
+    #[cfg(debug_assertions)]
+    for vec in chunk.rows() {
+        let norm = vec.iter().fold(0.0f32, |prev, x| prev + x * x).sqrt();
+        debug_assert!((norm - 1.0f32).abs() < 0.001f32, "Denormal vector detected");
+    }
 
     let duration = Instant::now() - start;
     println!(
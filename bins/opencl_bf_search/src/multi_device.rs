@@ -0,0 +1,167 @@
+use crate::opencl::build_dot_product_program;
+use crate::opencl::WorkGroupSize;
+use ocl::{Buffer, Context, Device, Kernel, MemFlags, Platform, Queue};
+use std::ops::Range;
+
+/// Calculates the dot products of the query against all vectors by splitting
+/// the vectors into one band of rows per device and processing the bands concurrently.
+///
+/// ## Arguments
+/// * `transposed` - The column-major `num_dims × num_vecs` data matrix.
+/// * `query` - The query vector.
+/// * `work_group` - The local work size of the kernels.
+///
+/// ## Returns
+/// The concatenated results of all devices.
+pub fn dot_product_multi_device(
+    platform: Platform,
+    devices: &[Device],
+    transposed: &[f32],
+    query: &[f32],
+    num_vecs: usize,
+    num_dims: usize,
+    work_group: WorkGroupSize,
+) -> Vec<f32> {
+    let bands = partition_rows(num_vecs, devices.len(), work_group.rows);
+    let mut results = vec![f32::NAN; num_vecs];
+
+    std::thread::scope(|scope| {
+        let mut remaining = results.as_mut_slice();
+        for (&device, band) in devices.iter().zip(bands) {
+            let (results, rest) = remaining.split_at_mut(band.len());
+            remaining = rest;
+
+            if band.is_empty() {
+                continue;
+            }
+
+            scope.spawn(move || {
+                let matrix = transpose_band(transposed, num_vecs, num_dims, band);
+                dot_product_on_device(
+                    platform, device, &matrix, query, num_dims, work_group, results,
+                );
+            });
+        }
+    });
+
+    results
+}
+
+/// Runs the dot product kernel on a single device.
+fn dot_product_on_device(
+    platform: Platform,
+    device: Device,
+    matrix: &[f32],
+    query: &[f32],
+    num_dims: usize,
+    work_group: WorkGroupSize,
+    results: &mut [f32],
+) {
+    let num_vecs = results.len();
+
+    let context = Context::builder()
+        .platform(platform)
+        .devices(device)
+        .build()
+        .unwrap();
+
+    let dot_product = build_dot_product_program(device, &context).unwrap();
+    let queue = Queue::new(&context, device, None).unwrap();
+
+    let matrix_buffer = Buffer::<f32>::builder()
+        .queue(queue.clone())
+        .flags(MemFlags::new().read_only().host_write_only())
+        .len(matrix.len())
+        .build()
+        .unwrap();
+
+    let vector_buffer = Buffer::<f32>::builder()
+        .queue(queue.clone())
+        .flags(MemFlags::new().read_only().host_write_only())
+        .len(num_dims)
+        .build()
+        .unwrap();
+
+    let result_buffer = Buffer::<f32>::builder()
+        .queue(queue.clone())
+        .flags(MemFlags::new().write_only().host_read_only())
+        .len(num_vecs)
+        .build()
+        .unwrap();
+
+    let dot_product_kernel = Kernel::builder()
+        .program(&dot_product)
+        .name("dot_product")
+        .queue(queue.clone())
+        .global_work_size(work_group.global_work_size(num_vecs))
+        .local_work_size(work_group.local_work_size())
+        .arg(&matrix_buffer)
+        .arg(&vector_buffer)
+        .arg(&result_buffer)
+        .arg_local::<f32>(work_group.local_mem_len())
+        .arg(num_vecs as u32)
+        .arg(num_dims as u32)
+        .build()
+        .unwrap();
+
+    matrix_buffer.cmd().write(matrix).enq().unwrap();
+    vector_buffer.cmd().write(query).enq().unwrap();
+    unsafe { dot_product_kernel.cmd().enq().unwrap() };
+    result_buffer.cmd().read(results).enq().unwrap();
+    queue.finish().unwrap();
+}
+
+/// Splits `num_vecs` rows into `num_parts` consecutive bands whose lengths
+/// are multiples of `granularity`, except possibly for the last one.
+fn partition_rows(num_vecs: usize, num_parts: usize, granularity: usize) -> Vec<Range<usize>> {
+    let num_parts = num_parts.max(1);
+    let rows_per_part = (num_vecs / num_parts) / granularity * granularity;
+
+    (0..num_parts)
+        .map(|part| {
+            let start = part * rows_per_part;
+            let end = if part + 1 == num_parts {
+                num_vecs
+            } else {
+                start + rows_per_part
+            };
+            start..end
+        })
+        .collect()
+}
+
+/// Extracts the columns of the band of rows from the column-major matrix.
+fn transpose_band(
+    transposed: &[f32],
+    num_vecs: usize,
+    num_dims: usize,
+    band: Range<usize>,
+) -> Vec<f32> {
+    let mut matrix = Vec::with_capacity(band.len() * num_dims);
+    for column in transposed.chunks_exact(num_vecs).take(num_dims) {
+        matrix.extend_from_slice(&column[band.clone()]);
+    }
+    matrix
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn partition_rows_works() {
+        assert_eq!(partition_rows(128, 2, 16), [0..64, 64..128]);
+        assert_eq!(partition_rows(160, 3, 16), [0..48, 48..96, 96..160]);
+        assert_eq!(partition_rows(16, 2, 16), [0..0, 0..16]);
+    }
+
+    #[test]
+    fn transpose_band_works() {
+        // Three vectors of two dimensions, stored column-major.
+        let transposed = [1.0, 2.0, 3.0, 10.0, 20.0, 30.0];
+        assert_eq!(
+            transpose_band(&transposed, 3, 2, 1..3),
+            [2.0, 3.0, 20.0, 30.0]
+        );
+    }
+}
@@ -0,0 +1,71 @@
+#define ROW_DIM 0
+#define COL_DIM 1
+
+// Reduces the partial sums of a work group's rows in log2(cols) steps,
+// leaving the row sums in the first column of WORK.
+void reduce_partial_sums(__local float *work, float sum) {
+    int rows = get_local_size(ROW_DIM); // rows in group
+    int cols = get_local_size(COL_DIM); // initial cols in group
+    int ii = get_local_id(ROW_DIM); // local row index in group, 0<=ii<rows
+    int jj = get_local_id(COL_DIM); // block index in column, 0<=jj<cols
+    work[ii + rows * jj] = sum;
+    barrier(CLK_LOCAL_MEM_FENCE); // sync group
+
+    while ( cols > 1 )
+    {
+        cols >>= 1;
+        if (jj < cols) {
+            work[ii + rows * jj] += work[ii + rows * (jj + cols)];
+        }
+        barrier(CLK_LOCAL_MEM_FENCE); // sync group
+    }
+}
+
+__kernel void euclidean_sq(const __global float *a,
+                           const __global float *x,
+                           __global float *y,
+                           __local float *work,
+                           unsigned int m,
+                           unsigned int n) {
+
+    // Compute partial squared distance
+    float sum = (float)0;
+    for (int k = get_global_id(COL_DIM); k < n; k += get_global_size(COL_DIM))
+    {
+        float diff = a[get_global_id(ROW_DIM) + m * k] - x[k];
+        sum += diff * diff;
+    }
+
+    reduce_partial_sums(work, sum);
+
+    // Write final result in Y
+    if ( get_local_id(COL_DIM) == 0 ) {
+        y[get_global_id(ROW_DIM)] = work[get_local_id(ROW_DIM)];
+    }
+}
+
+__kernel void cosine(const __global float *a,
+                     const __global float *x,
+                     __global float *y,
+                     __local float *work,
+                     unsigned int m,
+                     unsigned int n,
+                     const __global float *norms,
+                     float query_norm) {
+
+    // Compute partial dot product
+    float sum = (float)0;
+    for (int k = get_global_id(COL_DIM); k < n; k += get_global_size(COL_DIM))
+    {
+        sum += a[get_global_id(ROW_DIM) + m * k] * x[k];
+    }
+
+    reduce_partial_sums(work, sum);
+
+    // Write final result in Y; zero vectors are treated as orthogonal
+    if ( get_local_id(COL_DIM) == 0 ) {
+        float denominator = norms[get_global_id(ROW_DIM)] * query_norm;
+        float dot = work[get_local_id(ROW_DIM)];
+        y[get_global_id(ROW_DIM)] = denominator > 0 ? dot / denominator : 0;
+    }
+}
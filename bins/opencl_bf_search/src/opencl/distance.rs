@@ -0,0 +1,34 @@
+use memchunk::{Metric, OPENCL_DOT_PRODUCT_SOURCE};
+use ocl::builders::DeviceSpecifier;
+use ocl::{Context, Program};
+
+const DISTANCE_SOURCE: &str = include_str!("distance.cl");
+
+/// The name of the kernel calculating the metric.
+///
+/// The cosine kernel takes the L2 norms of the data vectors as an additional buffer
+/// and the L2 norm of the query as an additional scalar argument.
+pub const fn kernel_name(metric: Metric) -> &'static str {
+    match metric {
+        Metric::Dot => "dot_product",
+        Metric::EuclideanSq => "euclidean_sq",
+        Metric::Cosine => "cosine",
+    }
+}
+
+/// Builds the program providing the kernel for the metric; see [`kernel_name`].
+pub fn build_distance_program<D: Into<DeviceSpecifier>>(
+    device: D,
+    context: &Context,
+    metric: Metric,
+) -> ocl::Result<Program> {
+    let source = match metric {
+        Metric::Dot => OPENCL_DOT_PRODUCT_SOURCE,
+        Metric::EuclideanSq | Metric::Cosine => DISTANCE_SOURCE,
+    };
+
+    Program::builder()
+        .devices(device)
+        .src(source)
+        .build(context)
+}
@@ -1,7 +1,8 @@
+use memchunk::OPENCL_DOT_PRODUCT_SOURCE;
 use ocl::builders::DeviceSpecifier;
 use ocl::{Context, Program};
 
-const DOT_PRODUCT_SOURCE: &str = include_str!("dot_product.cl");
+const DOT_PRODUCT_F64_SOURCE: &str = include_str!("dot_product_f64.cl");
 
 pub fn build_dot_product_program<D: Into<DeviceSpecifier>>(
     device: D,
@@ -9,6 +10,19 @@ pub fn build_dot_product_program<D: Into<DeviceSpecifier>>(
 ) -> ocl::Result<Program> {
     Program::builder()
         .devices(device)
-        .src(DOT_PRODUCT_SOURCE)
+        .src(OPENCL_DOT_PRODUCT_SOURCE)
+        .build(context)
+}
+
+/// Builds the double-precision `dot_product_f64` kernel.
+///
+/// The device must support the `cl_khr_fp64` extension; see [`device_supports_fp64`](super::device_supports_fp64).
+pub fn build_dot_product_program_f64<D: Into<DeviceSpecifier>>(
+    device: D,
+    context: &Context,
+) -> ocl::Result<Program> {
+    Program::builder()
+        .devices(device)
+        .src(DOT_PRODUCT_F64_SOURCE)
         .build(context)
 }
@@ -1,17 +1,26 @@
+mod distance;
 mod dot_product;
 mod dot_topk;
 mod priority_queue;
+mod work_group;
 
 use clap::ArgMatches;
 use colored::Colorize;
-pub use dot_product::build_dot_product_program;
+pub use distance::{build_distance_program, kernel_name};
+pub use dot_product::{build_dot_product_program, build_dot_product_program_f64};
+use ocl::enums::{DeviceInfo, DeviceInfoResult};
 use ocl::{Device, Platform};
+pub use priority_queue::TopKReduction;
+pub use work_group::WorkGroupSize;
 
-pub fn ocl_print_platforms() {
+/// Prints the available OpenCL platforms and their devices.
+///
+/// Returns `false` if no platform is available.
+pub fn ocl_print_platforms() -> bool {
     let platforms = Platform::list();
     if platforms.is_empty() {
         eprintln!("No OpenCL platforms detected");
-        return;
+        return false;
     }
 
     for (pid, platform) in platforms.iter().enumerate() {
@@ -49,11 +58,26 @@ pub fn ocl_print_platforms() {
             }
         }
     }
+
+    true
+}
+
+/// Determines whether the device supports double-precision floating point values.
+pub fn device_supports_fp64(device: &Device) -> bool {
+    match device.info(DeviceInfo::Extensions) {
+        Ok(DeviceInfoResult::Extensions(extensions)) => extensions
+            .split_whitespace()
+            .any(|extension| extension == "cl_khr_fp64"),
+        _ => false,
+    }
 }
 
 pub struct OpenClDeviceSelection {
     pub platform: Platform,
+    /// The first selected device.
     pub device: Device,
+    /// All selected devices, starting with [`OpenClDeviceSelection::device`].
+    pub devices: Vec<Device>,
 }
 
 pub fn get_opencl_selection(matches: &ArgMatches) -> Option<OpenClDeviceSelection> {
@@ -67,10 +91,10 @@ pub fn get_opencl_selection(matches: &ArgMatches) -> Option<OpenClDeviceSelectio
         .unwrap_or(&0)
         .to_owned();
 
-    let did = matches
-        .get_one::<usize>("ocl-device-id")
-        .unwrap_or(&0)
-        .to_owned();
+    let dids: Vec<usize> = matches
+        .get_many::<usize>("ocl-device-id")
+        .map(|ids| ids.copied().collect())
+        .unwrap_or_else(|| vec![0]);
 
     // This should already be validated by the argument parser.
     debug_assert!(pid < platforms.len(), "platform ID out of bounds");
@@ -83,7 +107,7 @@ pub fn get_opencl_selection(matches: &ArgMatches) -> Option<OpenClDeviceSelectio
         name = name.green()
     );
 
-    let devices = match Device::list_all(&platform) {
+    let available = match Device::list_all(&platform) {
         Ok(devices) => devices,
         Err(e) => {
             eprintln!("The selected platform has no available devices: {e}");
@@ -91,23 +115,31 @@ pub fn get_opencl_selection(matches: &ArgMatches) -> Option<OpenClDeviceSelectio
         }
     };
 
-    if did >= devices.len() {
-        eprintln!(
-            "Unable to select device {did} for platform {pid}",
+    let mut devices = Vec::with_capacity(dids.len());
+    for did in dids {
+        if did >= available.len() {
+            eprintln!(
+                "Unable to select device {did} for platform {pid}",
+                did = format!("{}", did).blue(),
+                pid = format!("{}", pid).green()
+            );
+            return None;
+        }
+
+        let device = available[did];
+        let name = device.name().unwrap_or(String::from("(unnamed)"));
+        println!(
+            "Using OpenCL platform {pid}'s device {did}: {name}",
+            pid = format!("{}", pid).green(),
             did = format!("{}", did).blue(),
-            pid = format!("{}", pid).green()
+            name = name.blue()
         );
-        return None;
+        devices.push(device);
     }
 
-    let device = devices[did];
-    let name = device.name().unwrap_or(String::from("(unnamed)"));
-    println!(
-        "Using OpenCL platform {pid}'s device {did}: {name}",
-        pid = format!("{}", pid).green(),
-        did = format!("{}", did).blue(),
-        name = name.blue()
-    );
-
-    Some(OpenClDeviceSelection { platform, device })
+    Some(OpenClDeviceSelection {
+        platform,
+        device: devices[0],
+        devices,
+    })
 }
@@ -1,34 +1,132 @@
-#![allow(dead_code)]
-
+use memchunk::{merge_topk, Entry};
 use ocl::core::DeviceInfoResult;
-use ocl::{Context, Device, Program};
+use ocl::{Buffer, Context, Device, Kernel, MemFlags, Program, Queue};
 
 // Requires the cl_khr_priority_queue extension
+#[allow(dead_code)]
 const EXTENSION_PRIORITY_QUEUE_SOURCE: &str = include_str!("topk_priority_queue_ext.cl");
 
 const MANUAL_PRIORITY_QUEUE_SOURCE: &str = include_str!("topk_manual.cl");
 
-pub fn build_priority_queue_program(device: &Device, context: &Context) -> ocl::Result<Program> {
-    // Check if the device supports the cl_khr_priority_queue extension
-    match device.info(ocl::enums::DeviceInfo::Extensions).unwrap() {
-        DeviceInfoResult::Extensions(extensions) => {
-            if !extensions.contains("cl_khr_priority_queue") {
-                eprintln!("👎 Device does not support the cl_khr_priority_queue extension.");
-
-                Program::builder()
-                    .devices(device)
-                    .src(MANUAL_PRIORITY_QUEUE_SOURCE)
-                    .build(context)
-            } else {
-                println!("🎉 Device support the cl_khr_priority_queue extension!");
-                let _program = Program::builder()
-                    .devices(device)
-                    .src(EXTENSION_PRIORITY_QUEUE_SOURCE)
-                    .build(context);
-
-                todo!("Ensure Kernel has the correct inputs");
-            }
+/// The number of work groups of the top-K kernel, each of which returns `K` candidates.
+const TOPK_WORK_GROUPS: usize = 64;
+
+/// The number of work items per work group of the top-K kernel; must be a power of two.
+const TOPK_WORK_GROUP_SIZE: usize = 64;
+
+/// Builds the program providing the `topk` kernel selecting the `k` largest scores per work group.
+pub fn build_priority_queue_program(
+    device: &Device,
+    context: &Context,
+    k: usize,
+) -> ocl::Result<Program> {
+    // The extension kernel does not return the indices of the scores yet,
+    // so the manual kernel is used either way.
+    if let Ok(DeviceInfoResult::Extensions(extensions)) =
+        device.info(ocl::enums::DeviceInfo::Extensions)
+    {
+        if extensions.contains("cl_khr_priority_queue") {
+            println!("🎉 Device support the cl_khr_priority_queue extension!");
         }
-        _ => unreachable!(),
+    }
+
+    Program::builder()
+        .devices(device)
+        .src(MANUAL_PRIORITY_QUEUE_SOURCE)
+        .cmplr_def("K", k as i32)
+        .build(context)
+}
+
+/// Selects the `K` largest scores of a result buffer on the device, so that only
+/// `K` candidates per work group need to be read back instead of all scores.
+pub struct TopKReduction<const K: usize> {
+    kernel: Kernel,
+    values: Buffer<f32>,
+    indices: Buffer<u32>,
+}
+
+impl<const K: usize> TopKReduction<K> {
+    /// Prepares the reduction of the `scores` buffer, enqueuing it on the specified queue.
+    ///
+    /// Using the queue that runs the kernel producing the scores orders the reduction after it.
+    pub fn new(
+        device: &Device,
+        context: &Context,
+        queue: &Queue,
+        scores: &Buffer<f32>,
+    ) -> ocl::Result<Self> {
+        let program = build_priority_queue_program(device, context, K)?;
+
+        let values = Buffer::<f32>::builder()
+            .queue(queue.clone())
+            .flags(MemFlags::new().write_only().host_read_only())
+            .len(TOPK_WORK_GROUPS * K)
+            .build()?;
+
+        let indices = Buffer::<u32>::builder()
+            .queue(queue.clone())
+            .flags(MemFlags::new().write_only().host_read_only())
+            .len(TOPK_WORK_GROUPS * K)
+            .build()?;
+
+        let kernel = Kernel::builder()
+            .program(&program)
+            .name("topk")
+            .queue(queue.clone())
+            .global_work_size(TOPK_WORK_GROUPS * TOPK_WORK_GROUP_SIZE)
+            .local_work_size(TOPK_WORK_GROUP_SIZE)
+            .arg(scores)
+            .arg(&values)
+            .arg(&indices)
+            .arg_local::<f32>(TOPK_WORK_GROUP_SIZE * K)
+            .arg_local::<u32>(TOPK_WORK_GROUP_SIZE * K)
+            .arg(scores.len() as u32)
+            .build()?;
+
+        Ok(Self {
+            kernel,
+            values,
+            indices,
+        })
+    }
+
+    /// Runs the reduction and merges the candidates of all work groups on the host.
+    ///
+    /// NaN scores are never selected.
+    pub fn run(&self) -> ocl::Result<[Entry; K]> {
+        unsafe { self.kernel.cmd().enq()? };
+
+        let mut values = vec![f32::NAN; TOPK_WORK_GROUPS * K];
+        let mut indices = vec![0u32; TOPK_WORK_GROUPS * K];
+        self.values.cmd().read(&mut values).enq()?;
+        self.indices.cmd().read(&mut indices).enq()?;
+
+        Ok(merge_candidates(&values, &indices))
+    }
+}
+
+/// Merges the sorted top-`K` candidate lists of the work groups.
+fn merge_candidates<const K: usize>(values: &[f32], indices: &[u32]) -> [Entry; K] {
+    let partials: Vec<[Entry; K]> = values
+        .chunks_exact(K)
+        .zip(indices.chunks_exact(K))
+        .map(|(values, indices)| {
+            std::array::from_fn(|i| Entry::new(indices[i] as usize, values[i]))
+        })
+        .collect();
+    merge_topk(&partials)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn merge_candidates_works() {
+        let values = [9.0, 5.0, 1.0, 8.0, 7.0, f32::NEG_INFINITY];
+        let indices = [3, 0, 4, 1, 2, u32::MAX];
+        let merged = merge_candidates::<3>(&values, &indices);
+        assert_eq!(merged.map(|entry| entry.index()), [3, 1, 2]);
+        assert_eq!(merged.map(|entry| entry.value()), [9.0, 8.0, 7.0]);
     }
 }
@@ -1,19 +1,84 @@
-__kernel void topk(__global float* data, __global float* topk, __global uint* topk_idx, uint data_size, uint topk_size) {
-    int gid = get_global_id(0);
-    float cur_val = data[gid];
-    uint cur_idx = gid;
-    if (gid < topk_size) {
-        topk[gid] = cur_val;
-        topk_idx[gid] = cur_idx;
+// The number of largest scores selected by each work group; defined by the host.
+#ifndef K
+#define K 16
+#endif
+
+// Selects the K largest scores of each work group's share of the scores.
+//
+// Each work item keeps a sorted top-K list of a strided subset of the scores in
+// private memory. The lists are then merged pairwise in local memory in log2(rows)
+// steps, so the work group size must be a power of two. NaN scores are never selected;
+// groups with fewer than K scores pad their results with -INFINITY.
+__kernel void topk(const __global float *scores,
+                   __global float *topk_values,
+                   __global uint *topk_indices,
+                   __local float *local_values,
+                   __local uint *local_indices,
+                   unsigned int num_scores) {
+    const uint lid = get_local_id(0);
+
+    // Private top-K list, sorted in descending order
+    float values[K];
+    uint indices[K];
+    for (int i = 0; i < K; ++i) {
+        values[i] = -INFINITY;
+        indices[i] = UINT_MAX;
     }
-    barrier(CLK_GLOBAL_MEM_FENCE);
-    for (int i = topk_size / 2; i > 0; i >>= 1) {
-        if (gid < i) {
-            if (cur_val > topk[gid + i]) {
-                topk[gid + i] = cur_val;
-                topk_idx[gid + i] = cur_idx;
+
+    for (uint s = get_global_id(0); s < num_scores; s += get_global_size(0)) {
+        float value = scores[s];
+        if (value > values[K - 1]) {
+            int j = K - 1;
+            while (j > 0 && values[j - 1] < value) {
+                values[j] = values[j - 1];
+                indices[j] = indices[j - 1];
+                --j;
             }
-            barrier(CLK_GLOBAL_MEM_FENCE);
+            values[j] = value;
+            indices[j] = s;
+        }
+    }
+
+    for (int i = 0; i < K; ++i) {
+        local_values[lid * K + i] = values[i];
+        local_indices[lid * K + i] = indices[i];
+    }
+    barrier(CLK_LOCAL_MEM_FENCE); // sync group
+
+    // Merge the lists of work items lid and lid + stride
+    for (uint stride = get_local_size(0) / 2; stride > 0; stride >>= 1) {
+        if (lid < stride) {
+            uint a = lid * K;
+            uint b = (lid + stride) * K;
+            for (int i = 0; i < K; ++i) {
+                if (local_values[a] >= local_values[b]) {
+                    values[i] = local_values[a];
+                    indices[i] = local_indices[a];
+                    ++a;
+                } else {
+                    values[i] = local_values[b];
+                    indices[i] = local_indices[b];
+                    ++b;
+                }
+            }
+        }
+        barrier(CLK_LOCAL_MEM_FENCE); // sync group
+
+        if (lid < stride) {
+            for (int i = 0; i < K; ++i) {
+                local_values[lid * K + i] = values[i];
+                local_indices[lid * K + i] = indices[i];
+            }
+        }
+        barrier(CLK_LOCAL_MEM_FENCE); // sync group
+    }
+
+    // Write the group's top-K list
+    if (lid == 0) {
+        uint offset = get_group_id(0) * K;
+        for (int i = 0; i < K; ++i) {
+            topk_values[offset + i] = local_values[i];
+            topk_indices[offset + i] = local_indices[i];
         }
     }
 }
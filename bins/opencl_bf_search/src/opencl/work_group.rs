@@ -0,0 +1,99 @@
+use ocl::Device;
+
+/// The local work size of the dot product kernels.
+///
+/// Each work group computes the dot products of `rows` data vectors, splitting each
+/// of them into `partials` partial sums that are then reduced in local memory.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct WorkGroupSize {
+    /// The number of rows processed by a work group.
+    pub rows: usize,
+    /// The number of partial sums per row; must be a power of two for the reduction.
+    pub partials: usize,
+}
+
+impl WorkGroupSize {
+    /// The local work size passed to the kernel builder.
+    pub const fn local_work_size(&self) -> [usize; 2] {
+        [self.rows, self.partials]
+    }
+
+    /// The global work size for processing the specified number of vectors,
+    /// which must be a multiple of [`WorkGroupSize::rows`].
+    pub const fn global_work_size(&self, num_vecs: usize) -> [usize; 2] {
+        [num_vecs, self.partials]
+    }
+
+    /// The number of elements of the local scratch memory of the kernels.
+    pub const fn local_mem_len(&self) -> usize {
+        self.rows * (self.partials + 1)
+    }
+
+    /// Ensures that the device supports work groups of this size.
+    pub fn validate(&self, device: &Device) -> Result<(), String> {
+        let max = device
+            .max_wg_size()
+            .map_err(|e| format!("Unable to determine the maximum work group size: {e}"))?;
+        let size = self.rows * self.partials;
+        if size > max {
+            return Err(format!(
+                "The work group size {rows}×{partials} = {size} exceeds the device maximum of {max}",
+                rows = self.rows,
+                partials = self.partials,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Ensures that the vectors fill at least one work group once their number is
+    /// truncated to a multiple of `alignment` and of the number of rows.
+    pub fn validate_num_vecs(&self, num_vecs: usize, alignment: usize) -> Result<(), String> {
+        let required = alignment.max(self.rows);
+        if num_vecs < required {
+            return Err(format!(
+                "The work group of {rows} rows requires at least {required} vectors, but only {num_vecs} are available",
+                rows = self.rows,
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Defaults to work groups of 16×16 work items.
+impl Default for WorkGroupSize {
+    fn default() -> Self {
+        Self {
+            rows: 16,
+            partials: 16,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sizes_work() {
+        let size = WorkGroupSize {
+            rows: 8,
+            partials: 32,
+        };
+        assert_eq!(size.local_work_size(), [8, 32]);
+        assert_eq!(size.global_work_size(1024), [1024, 32]);
+        assert_eq!(size.local_mem_len(), 8 * 33);
+    }
+
+    #[test]
+    fn validate_num_vecs_rejects_oversized_work_groups() {
+        let size = WorkGroupSize {
+            rows: 256,
+            partials: 1,
+        };
+        assert!(size.validate_num_vecs(256, 32).is_ok());
+        assert!(size.validate_num_vecs(255, 32).is_err());
+        assert!(WorkGroupSize::default().validate_num_vecs(31, 32).is_err());
+    }
+}
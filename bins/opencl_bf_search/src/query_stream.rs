@@ -0,0 +1,156 @@
+use crate::opencl::WorkGroupSize;
+use ocl::{Buffer, Context, Device, Event, Kernel, MemFlags, Program, Queue};
+
+/// Processes a stream of queries against a data matrix resident on the device.
+///
+/// Query uploads and kernel executions are double-buffered: while the kernel
+/// for query `N` runs, query `N + 1` is uploaded into the second slot
+/// using a separate queue.
+pub struct QueryStream {
+    vector_queue: Queue,
+    result_queue: Queue,
+    slots: [Slot; 2],
+    num_vecs: usize,
+}
+
+/// The buffers and kernel of one query in flight.
+struct Slot {
+    vector_buffer: Buffer<f32>,
+    result_buffer: Buffer<f32>,
+    kernel: Kernel,
+}
+
+impl QueryStream {
+    /// Uploads the data matrix and prepares the buffers for two queries in flight.
+    ///
+    /// ## Arguments
+    /// * `program` - The program providing the `dot_product` kernel.
+    /// * `transposed` - The column-major `num_dims × num_vecs` data matrix.
+    /// * `work_group` - The local work size of the kernel; `num_vecs` must be a multiple
+    ///   of its number of rows.
+    pub fn new(
+        context: &Context,
+        device: Device,
+        program: &Program,
+        transposed: &[f32],
+        num_vecs: usize,
+        num_dims: usize,
+        work_group: WorkGroupSize,
+    ) -> ocl::Result<Self> {
+        let matrix_queue = Queue::new(context, device, None)?;
+        let vector_queue = Queue::new(context, device, None)?;
+        let result_queue = Queue::new(context, device, None)?;
+
+        let matrix_buffer = Buffer::<f32>::builder()
+            .queue(matrix_queue.clone())
+            .flags(MemFlags::new().read_only().host_write_only())
+            .len(num_vecs * num_dims)
+            .build()?;
+        matrix_buffer.cmd().write(transposed).enq()?;
+        matrix_queue.finish()?;
+
+        let create_slot = || -> ocl::Result<Slot> {
+            let vector_buffer = Buffer::<f32>::builder()
+                .queue(vector_queue.clone())
+                .flags(MemFlags::new().read_only().host_write_only())
+                .len(num_dims)
+                .build()?;
+
+            let result_buffer = Buffer::<f32>::builder()
+                .queue(result_queue.clone())
+                .flags(MemFlags::new().write_only().host_read_only())
+                .len(num_vecs)
+                .build()?;
+
+            let kernel = Kernel::builder()
+                .program(program)
+                .name("dot_product")
+                .queue(result_queue.clone())
+                .global_work_size(work_group.global_work_size(num_vecs))
+                .local_work_size(work_group.local_work_size())
+                .arg(&matrix_buffer)
+                .arg(&vector_buffer)
+                .arg(&result_buffer)
+                .arg_local::<f32>(work_group.local_mem_len())
+                .arg(num_vecs as u32)
+                .arg(num_dims as u32)
+                .build()?;
+
+            Ok(Slot {
+                vector_buffer,
+                result_buffer,
+                kernel,
+            })
+        };
+
+        Ok(Self {
+            slots: [create_slot()?, create_slot()?],
+            vector_queue,
+            result_queue,
+            num_vecs,
+        })
+    }
+
+    /// Calculates the dot products of every query against the data matrix,
+    /// returning one result vector per query.
+    pub fn process<'q, I>(&self, queries: I) -> ocl::Result<Vec<Vec<f32>>>
+    where
+        I: IntoIterator<Item = &'q [f32]>,
+    {
+        let mut queries = queries.into_iter();
+        let mut results = Vec::new();
+
+        let mut upload = match queries.next() {
+            Some(query) => self.upload(&self.slots[0], query)?,
+            None => return Ok(results),
+        };
+
+        for n in 0.. {
+            let slot = &self.slots[n % 2];
+
+            let mut kernel_event = Event::empty();
+            unsafe {
+                slot.kernel
+                    .cmd()
+                    .ewait(&upload)
+                    .enew(&mut kernel_event)
+                    .enq()?
+            };
+            self.result_queue.flush()?;
+
+            // Upload the next query into the other slot while the kernel runs.
+            // The other slot is idle since its results were read in the previous iteration.
+            let next = match queries.next() {
+                Some(query) => Some(self.upload(&self.slots[(n + 1) % 2], query)?),
+                None => None,
+            };
+
+            let mut result = vec![f32::NAN; self.num_vecs];
+            slot.result_buffer
+                .cmd()
+                .read(&mut result)
+                .ewait(&kernel_event)
+                .enq()?;
+            results.push(result);
+
+            match next {
+                Some(event) => upload = event,
+                None => break,
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Writes the query into the slot's vector buffer, returning the completion event.
+    fn upload(&self, slot: &Slot, query: &[f32]) -> ocl::Result<Event> {
+        let mut event = Event::empty();
+        slot.vector_buffer
+            .cmd()
+            .write(query)
+            .enew(&mut event)
+            .enq()?;
+        self.vector_queue.flush()?;
+        Ok(event)
+    }
+}
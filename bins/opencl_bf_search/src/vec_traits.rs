@@ -16,6 +16,18 @@ pub trait DotProduct {
     fn dot_product<O: AsRef<[Self::Output]>>(&self, other: O) -> Self::Output;
 }
 
+pub trait CosineSimilarity {
+    type Output;
+
+    fn cosine_similarity<O: AsRef<[Self::Output]>>(&self, other: O) -> Self::Output;
+}
+
+pub trait L1Distance {
+    type Output;
+
+    fn l1_distance<O: AsRef<[Self::Output]>>(&self, other: O) -> Self::Output;
+}
+
 impl<T> L2Norm for T
 where
     T: AsRef<[f32]>,
@@ -43,10 +55,14 @@ where
     T: AsMut<[f32]> + AsRef<[f32]>,
 {
     fn normalize_in_place(&mut self) {
-        let inv_norm = 1.0 / self.l2_norm();
-        for x in self.as_mut().iter_mut() {
-            *x *= inv_norm;
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        if avx2::is_supported() {
+            // SAFETY: Support for the required instruction sets was checked above.
+            unsafe { avx2::normalize_in_place(self.as_mut()) };
+            return;
         }
+
+        normalize_in_place_scalar(self.as_mut());
     }
 
     fn normalize_into<D: AsMut<[Self::Output]>>(&self, mut dest: D) {
@@ -57,6 +73,71 @@ where
     }
 }
 
+/// Normalizes every row of the row-major `data` matrix to unit length in place.
+pub fn normalize_batch(data: &mut [f32], num_dims: usize) {
+    debug_assert_eq!(data.len() % num_dims, 0, "data buffer dimension mismatch");
+    for mut row in data.chunks_exact_mut(num_dims) {
+        row.normalize_in_place();
+    }
+}
+
+fn normalize_in_place_scalar(vec: &mut [f32]) {
+    let inv_norm = 1.0 / vec.l2_norm();
+    for x in vec.iter_mut() {
+        *x *= inv_norm;
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod avx2 {
+    use std::arch::x86_64::*;
+
+    /// The number of [`f32`] lanes in an AVX2 register.
+    const LANES: usize = 8;
+
+    /// Determines whether the current CPU supports the required instruction sets.
+    pub fn is_supported() -> bool {
+        is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma")
+    }
+
+    /// Normalizes the vector like the scalar path, eight lanes at a time.
+    ///
+    /// ## Safety
+    /// The CPU must support AVX2 and FMA.
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn normalize_in_place(vec: &mut [f32]) {
+        let simd_len = vec.len() - vec.len() % LANES;
+        let ptr = vec.as_mut_ptr();
+
+        let mut sum = _mm256_setzero_ps();
+        for i in (0..simd_len).step_by(LANES) {
+            let x = _mm256_loadu_ps(ptr.add(i));
+            sum = _mm256_fmadd_ps(x, x, sum);
+        }
+
+        let mut lanes = [0.0f32; LANES];
+        _mm256_storeu_ps(lanes.as_mut_ptr(), sum);
+        let norm_sq =
+            lanes.iter().sum::<f32>() + vec[simd_len..].iter().map(|x| x * x).sum::<f32>();
+
+        // Zero vectors are left unchanged, matching the scalar path.
+        if norm_sq == 0.0 {
+            return;
+        }
+
+        let inv_norm = 1.0 / norm_sq.sqrt();
+        let scale = _mm256_set1_ps(inv_norm);
+        for i in (0..simd_len).step_by(LANES) {
+            let x = _mm256_loadu_ps(ptr.add(i));
+            _mm256_storeu_ps(ptr.add(i), _mm256_mul_ps(x, scale));
+        }
+
+        for x in vec[simd_len..].iter_mut() {
+            *x *= inv_norm;
+        }
+    }
+}
+
 impl<T> DotProduct for T
 where
     T: AsRef<[f32]>,
@@ -72,6 +153,39 @@ where
     }
 }
 
+impl<T> CosineSimilarity for T
+where
+    T: AsRef<[f32]>,
+{
+    type Output = f32;
+
+    fn cosine_similarity<O: AsRef<[Self::Output]>>(&self, other: O) -> Self::Output {
+        let other = other.as_ref();
+
+        // The similarity to a zero vector is undefined; we treat it as orthogonal.
+        if self.l2_norm_sq() == 0.0 || other.l2_norm_sq() == 0.0 {
+            return 0.0;
+        }
+
+        self.dot_product(other) / (self.l2_norm() * other.l2_norm())
+    }
+}
+
+impl<T> L1Distance for T
+where
+    T: AsRef<[f32]>,
+{
+    type Output = f32;
+
+    fn l1_distance<O: AsRef<[Self::Output]>>(&self, other: O) -> Self::Output {
+        self.as_ref()
+            .iter()
+            .zip(other.as_ref().iter())
+            .map(|(x, y)| (x - y).abs())
+            .sum()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -114,6 +228,35 @@ mod test {
         assert_eq!(normalized[2], 0.0);
     }
 
+    #[test]
+    fn normalize_batch_works() {
+        let mut data = vec![1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 3.0, 0.0, 4.0];
+        normalize_batch(&mut data, 3);
+
+        assert_relative_eq!(data[0], 0.5 * f32::sqrt(2.0), epsilon = 1e-5);
+        assert_relative_eq!(data[1], 0.5 * f32::sqrt(2.0), epsilon = 1e-5);
+        assert_eq!(&data[3..6], [0.0, 0.0, 0.0]);
+        assert_relative_eq!(data[6], 0.6, epsilon = 1e-5);
+        assert_relative_eq!(data[8], 0.8, epsilon = 1e-5);
+    }
+
+    #[test]
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    fn avx2_normalize_matches_scalar() {
+        if !avx2::is_supported() {
+            return;
+        }
+
+        let mut expected: Vec<f32> = (0..389).map(|i| (i as f32 * 0.37).sin()).collect();
+        let mut vec = expected.clone();
+        normalize_in_place_scalar(&mut expected);
+        unsafe { avx2::normalize_in_place(&mut vec) };
+
+        for (x, y) in vec.iter().zip(expected.iter()) {
+            assert_relative_eq!(x, y, epsilon = 1e-6);
+        }
+    }
+
     #[test]
     fn dot_product_works() {
         let lhs = vec![0.5, 2.0, 0.0];
@@ -125,4 +268,30 @@ mod test {
             epsilon = 0.0
         );
     }
+
+    #[test]
+    fn cosine_similarity_works() {
+        let lhs = vec![1.0, 1.0, 0.0];
+        let rhs = vec![2.0, 0.0, 0.0];
+        assert_relative_eq!(
+            lhs.cosine_similarity(&rhs),
+            0.5 * f32::sqrt(2.0),
+            epsilon = 1e-5
+        );
+    }
+
+    #[test]
+    fn cosine_similarity_of_zero_vectors_is_zero() {
+        let lhs = vec![1.0, 1.0, 0.0];
+        assert_eq!(lhs.cosine_similarity([0.0, 0.0, 0.0]), 0.0);
+        assert_eq!([0.0, 0.0, 0.0].cosine_similarity(&lhs), 0.0);
+        assert_eq!([0.0, 0.0].cosine_similarity([0.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn l1_distance_works() {
+        let lhs = vec![0.5, 2.0, 0.0];
+        let rhs = vec![0.1, 1.0, -1.0];
+        assert_relative_eq!(lhs.l1_distance(rhs), 0.4 + 1.0 + 1.0, epsilon = 1e-6);
+    }
 }
@@ -1,7 +1,9 @@
 #![allow(dead_code)]
 
+use crate::vec_traits::normalize_batch;
 use rand::{Rng, SeedableRng};
 use rand_xoshiro::Xoshiro128Plus;
+use rayon::prelude::*;
 
 #[derive(Debug)]
 pub struct Vecgen {
@@ -28,6 +30,55 @@ impl Vecgen {
         self.rng.fill(dest)
     }
 
+    /// Fills a slice with normally distributed floating point values
+    /// using the Box-Muller transform.
+    pub fn fill_gaussian(&mut self, dest: &mut [f32], mean: f32, std: f32) {
+        for pair in dest.chunks_mut(2) {
+            // Shift the first sample from [0, 1) to (0, 1] to keep the logarithm finite.
+            let u1 = 1.0 - self.rng.gen::<f32>();
+            let u2 = self.rng.gen::<f32>();
+
+            let radius = (-2.0 * u1.ln()).sqrt();
+            let (sin, cos) = (std::f32::consts::TAU * u2).sin_cos();
+
+            pair[0] = mean + std * radius * cos;
+            if let Some(second) = pair.get_mut(1) {
+                *second = mean + std * radius * sin;
+            }
+        }
+    }
+
+    /// Fills the row-major `dest` matrix with random unit vectors of `num_dims` dimensions.
+    ///
+    /// The rows are drawn from a standard normal distribution before normalization,
+    /// so that their directions are uniformly distributed on the unit hypersphere.
+    pub fn fill_normalized(&mut self, dest: &mut [f32], num_dims: usize) {
+        self.fill_gaussian(dest, 0.0, 1.0);
+        normalize_batch(dest, num_dims);
+    }
+
+    /// Fills a slice with random floating point values in parallel.
+    ///
+    /// The slice is split into segments of `chunk_len` values, each of which is filled
+    /// by its own generator, forked in sequence from this one. The result therefore only
+    /// depends on the seed and `chunk_len`, not on the number of threads.
+    pub fn fill_parallel<Q: AsMut<[f32]>>(&self, mut dest: Q, chunk_len: usize) {
+        assert!(chunk_len > 0, "chunk length must be positive");
+        let dest = dest.as_mut();
+
+        let mut rng = self.fork();
+        let mut rngs = Vec::with_capacity((dest.len() + chunk_len - 1) / chunk_len);
+        for _ in dest.chunks(chunk_len) {
+            let next = rng.fork();
+            rngs.push(rng);
+            rng = next;
+        }
+
+        dest.par_chunks_mut(chunk_len)
+            .zip(rngs)
+            .for_each(|(chunk, mut rng)| rng.fill(chunk));
+    }
+
     /// Forks this rng to create a new instance capable of creating
     /// 2^64 non-overlapping floating-point numbers.
     pub fn fork(&self) -> Self {
@@ -64,6 +115,55 @@ mod test {
         assert_relative_eq!(vector[0], 0.87221956f32, epsilon = 1e-5);
     }
 
+    #[test]
+    fn fill_gaussian_works() {
+        let mut rng = Vecgen::new_from_seed(1337);
+        let mut vector = vec![0f32; 100_001];
+        rng.fill_gaussian(&mut vector, 1.0, 2.0);
+
+        let n = vector.len() as f32;
+        let mean = vector.iter().sum::<f32>() / n;
+        let variance = vector.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / n;
+        assert_relative_eq!(mean, 1.0, epsilon = 0.05);
+        assert_relative_eq!(variance.sqrt(), 2.0, epsilon = 0.05);
+
+        let mut rng = Vecgen::new_from_seed(1337);
+        let mut repeated = vec![0f32; 100_001];
+        rng.fill_gaussian(&mut repeated, 1.0, 2.0);
+        assert_eq!(vector, repeated);
+    }
+
+    #[test]
+    fn fill_normalized_works() {
+        let num_dims = 384;
+        let mut rng = Vecgen::new_from_seed(1337);
+        let mut data = vec![0f32; 16 * num_dims];
+        rng.fill_normalized(&mut data, num_dims);
+
+        for row in data.chunks_exact(num_dims) {
+            let norm = row.iter().map(|x| x * x).sum::<f32>().sqrt();
+            assert_relative_eq!(norm, 1.0, epsilon = 1e-5);
+        }
+        assert_ne!(data[..num_dims], data[num_dims..2 * num_dims]);
+    }
+
+    #[test]
+    fn fill_parallel_is_deterministic() {
+        let rng = Vecgen::new_from_seed(1337);
+        let mut vector = vec![0f32; 10_000];
+        rng.fill_parallel(&mut vector, 1024);
+
+        let mut repeated = vec![0f32; 10_000];
+        Vecgen::new_from_seed(1337).fill_parallel(&mut repeated, 1024);
+        assert_eq!(vector, repeated);
+
+        // The first segment is filled by the first fork.
+        let mut expected = [0f32; 1024];
+        rng.fork().fill(&mut expected);
+        assert_eq!(vector[..1024], expected);
+        assert_ne!(vector[..1024], vector[1024..2048]);
+    }
+
     #[test]
     fn fork_works() {
         let rng = Vecgen::new_from_seed(1337);
@@ -1,9 +1,16 @@
+use std::error::Error;
 use std::fmt::{Display, Formatter};
-use std::ops::{Deref, Mul, Range};
+use std::ops::{Add, Deref, Mul, Range, Sub};
 
+/// A number of vectors.
+///
+/// Adding and subtracting saturates at zero and [`usize::MAX`] instead of overflowing.
 #[derive(Default, Debug, Copy, Clone)]
 pub struct NumVectors(usize);
 
+/// A number of dimensions.
+///
+/// Adding and subtracting saturates at zero and [`usize::MAX`] instead of overflowing.
 #[derive(Default, Debug, Copy, Clone)]
 pub struct NumDimensions(usize);
 
@@ -13,13 +20,120 @@ impl NumVectors {
         0..self.0
     }
 
+    /// Splits [`NumVectors::range`] into `parts` contiguous, non-overlapping ranges
+    /// of roughly equal length, e.g. for distributing vectors across threads.
+    ///
+    /// The first `len % parts` ranges hold one vector more than the others.
+    /// If there are fewer vectors than parts, the trailing ranges are empty.
+    ///
+    /// ## Panics
+    /// Panics if `parts` is zero.
+    pub fn split_into(&self, parts: usize) -> Vec<Range<usize>> {
+        assert_ne!(parts, 0, "the number of parts must be positive");
+        let base = self.0 / parts;
+        let remainder = self.0 % parts;
+
+        let mut start = 0;
+        (0..parts)
+            .map(|i| {
+                let len = base + usize::from(i < remainder);
+                let range = start..start + len;
+                start += len;
+                range
+            })
+            .collect()
+    }
+
     #[inline(always)]
     pub const fn into_inner(self) -> usize {
         self.0
     }
+
+    /// Calculates the number of elements of the vectors, returning `None` on overflow.
+    #[inline(always)]
+    pub const fn checked_mul(self, rhs: NumDimensions) -> Option<usize> {
+        self.0.checked_mul(rhs.0)
+    }
+
+    /// Subtracts `rhs`, returning zero instead of underflowing.
+    #[inline(always)]
+    pub const fn saturating_sub(self, rhs: usize) -> Self {
+        Self(self.0.saturating_sub(rhs))
+    }
+
+    /// Subtracts `rhs`, returning `None` on underflow.
+    #[inline(always)]
+    pub const fn checked_sub(self, rhs: usize) -> Option<Self> {
+        match self.0.checked_sub(rhs) {
+            Some(value) => Some(Self(value)),
+            None => None,
+        }
+    }
+
+    /// Calculates the number of bytes required to store the vectors with
+    /// `element_size` bytes per element, returning `None` on overflow.
+    #[inline(always)]
+    pub const fn checked_bytes(
+        self,
+        num_dims: NumDimensions,
+        element_size: usize,
+    ) -> Option<usize> {
+        match self.checked_mul(num_dims) {
+            Some(num_elems) => num_elems.checked_mul(element_size),
+            None => None,
+        }
+    }
 }
 
+/// An error indicating an invalid number of dimensions.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DimensionError {
+    /// The number of dimensions is zero.
+    Zero,
+    /// The number of dimensions is not a multiple of [`NumDimensions::SIMD_ALIGNMENT`].
+    NotSimdAligned(usize),
+}
+
+impl Display for DimensionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DimensionError::Zero => write!(f, "the number of dimensions must be positive"),
+            DimensionError::NotSimdAligned(value) => write!(
+                f,
+                "the number of dimensions must be a multiple of {}, got {value}",
+                NumDimensions::SIMD_ALIGNMENT
+            ),
+        }
+    }
+}
+
+impl Error for DimensionError {}
+
 impl NumDimensions {
+    /// The number of dimensions SIMD-friendly vectors are a multiple of.
+    pub const SIMD_ALIGNMENT: usize = 16;
+
+    /// 256 dimensions.
+    pub const DIMS_256: Self = Self(256);
+
+    /// 384 dimensions, e.g. as produced by `all-MiniLM-L6-v2`.
+    pub const DIMS_384: Self = Self(384);
+
+    /// 512 dimensions.
+    pub const DIMS_512: Self = Self(512);
+
+    /// 768 dimensions, e.g. as produced by BERT-base models.
+    pub const DIMS_768: Self = Self(768);
+
+    /// 1024 dimensions, e.g. as produced by BERT-large models.
+    pub const DIMS_1024: Self = Self(1024);
+
+    /// 1536 dimensions, e.g. as produced by OpenAI `text-embedding-ada-002`.
+    pub const DIMS_1536: Self = Self(1536);
+
+    /// 3072 dimensions, e.g. as produced by OpenAI `text-embedding-3-large`.
+    pub const DIMS_3072: Self = Self(3072);
+
     #[inline(always)]
     pub const fn range(&self) -> Range<usize> {
         0..self.0
@@ -29,6 +143,49 @@ impl NumDimensions {
     pub const fn into_inner(self) -> usize {
         self.0
     }
+
+    /// Creates a number of dimensions that is a positive multiple of
+    /// [`NumDimensions::SIMD_ALIGNMENT`].
+    ///
+    /// This is the preferred way of validating user input; the `From` conversions
+    /// accept any value.
+    pub const fn try_new_simd(value: usize) -> Result<Self, DimensionError> {
+        if value == 0 {
+            Err(DimensionError::Zero)
+        } else if value % Self::SIMD_ALIGNMENT != 0 {
+            Err(DimensionError::NotSimdAligned(value))
+        } else {
+            Ok(Self(value))
+        }
+    }
+
+    /// Determines whether the number of dimensions is a positive multiple of
+    /// [`NumDimensions::SIMD_ALIGNMENT`].
+    #[inline(always)]
+    pub const fn is_simd_aligned(&self) -> bool {
+        self.0 > 0 && self.0 % Self::SIMD_ALIGNMENT == 0
+    }
+
+    /// Calculates the number of elements of the vectors, returning `None` on overflow.
+    #[inline(always)]
+    pub const fn checked_mul(self, rhs: NumVectors) -> Option<usize> {
+        self.0.checked_mul(rhs.0)
+    }
+
+    /// Subtracts `rhs`, returning zero instead of underflowing.
+    #[inline(always)]
+    pub const fn saturating_sub(self, rhs: usize) -> Self {
+        Self(self.0.saturating_sub(rhs))
+    }
+
+    /// Subtracts `rhs`, returning `None` on underflow.
+    #[inline(always)]
+    pub const fn checked_sub(self, rhs: usize) -> Option<Self> {
+        match self.0.checked_sub(rhs) {
+            Some(value) => Some(Self(value)),
+            None => None,
+        }
+    }
 }
 
 impl IntoIterator for NumVectors {
@@ -51,6 +208,8 @@ impl IntoIterator for NumDimensions {
     }
 }
 
+/// Converts any value without validation; see [`NumDimensions::try_new_simd`]
+/// for a validating alternative.
 impl From<usize> for NumDimensions {
     fn from(value: usize) -> Self {
         Self(value)
@@ -175,6 +334,70 @@ impl Mul<NumDimensions> for usize {
     }
 }
 
+impl Add<NumVectors> for NumVectors {
+    type Output = NumVectors;
+
+    fn add(self, rhs: NumVectors) -> Self::Output {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl Add<usize> for NumVectors {
+    type Output = NumVectors;
+
+    fn add(self, rhs: usize) -> Self::Output {
+        Self(self.0.saturating_add(rhs))
+    }
+}
+
+impl Sub<NumVectors> for NumVectors {
+    type Output = NumVectors;
+
+    fn sub(self, rhs: NumVectors) -> Self::Output {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl Sub<usize> for NumVectors {
+    type Output = NumVectors;
+
+    fn sub(self, rhs: usize) -> Self::Output {
+        Self(self.0.saturating_sub(rhs))
+    }
+}
+
+impl Add<NumDimensions> for NumDimensions {
+    type Output = NumDimensions;
+
+    fn add(self, rhs: NumDimensions) -> Self::Output {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl Add<usize> for NumDimensions {
+    type Output = NumDimensions;
+
+    fn add(self, rhs: usize) -> Self::Output {
+        Self(self.0.saturating_add(rhs))
+    }
+}
+
+impl Sub<NumDimensions> for NumDimensions {
+    type Output = NumDimensions;
+
+    fn sub(self, rhs: NumDimensions) -> Self::Output {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl Sub<usize> for NumDimensions {
+    type Output = NumDimensions;
+
+    fn sub(self, rhs: usize) -> Self::Output {
+        Self(self.0.saturating_sub(rhs))
+    }
+}
+
 impl Display for NumVectors {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -186,3 +409,88 @@ impl Display for NumDimensions {
         write!(f, "{}", self.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_and_sub_work() {
+        let num_vecs = NumVectors::from(100usize) + 28 - NumVectors::from(64usize);
+        assert_eq!(*num_vecs, 64);
+        assert_eq!(*num_vecs.saturating_sub(100), 0);
+        assert!(num_vecs.checked_sub(65).is_none());
+
+        let num_dims = NumDimensions::DIMS_384 + NumDimensions::from(16usize) - 16;
+        assert_eq!(*num_dims, 384);
+        assert_eq!(*num_dims.saturating_sub(400), 0);
+    }
+
+    #[test]
+    fn add_and_sub_saturate() {
+        let num_vecs = NumVectors::from(10usize);
+        assert_eq!(*(num_vecs - 11), 0);
+        assert_eq!(*(num_vecs - NumVectors::from(usize::MAX)), 0);
+        assert_eq!(*(num_vecs + usize::MAX), usize::MAX);
+        assert_eq!(*(NumVectors::from(usize::MAX) + num_vecs), usize::MAX);
+
+        let num_dims = NumDimensions::from(10usize);
+        assert_eq!(*(num_dims - 11), 0);
+        assert_eq!(*(num_dims - NumDimensions::from(usize::MAX)), 0);
+        assert_eq!(*(num_dims + usize::MAX), usize::MAX);
+        assert_eq!(*(NumDimensions::from(usize::MAX) + num_dims), usize::MAX);
+    }
+
+    #[test]
+    fn split_into_covers_all_vectors() {
+        for (len, parts) in [(10usize, 3), (2, 4), (0, 2), (64, 8)] {
+            let ranges = NumVectors::from(len).split_into(parts);
+            assert_eq!(ranges.len(), parts);
+            assert_eq!(ranges[0].start, 0);
+            assert_eq!(ranges[parts - 1].end, len);
+            for pair in ranges.windows(2) {
+                assert_eq!(pair[0].end, pair[1].start);
+                assert!(pair[0].len() >= pair[1].len());
+                assert!(pair[0].len() - pair[1].len() <= 1);
+            }
+        }
+
+        assert_eq!(NumVectors::from(10usize).split_into(3), [0..4, 4..7, 7..10]);
+    }
+
+    #[test]
+    fn try_new_simd_works() {
+        assert_eq!(*NumDimensions::try_new_simd(384).unwrap(), 384);
+        assert_eq!(
+            NumDimensions::try_new_simd(0).unwrap_err(),
+            DimensionError::Zero
+        );
+        assert_eq!(
+            NumDimensions::try_new_simd(300).unwrap_err(),
+            DimensionError::NotSimdAligned(300)
+        );
+        assert!(NumDimensions::DIMS_1536.is_simd_aligned());
+        assert!(!NumDimensions::from(300usize).is_simd_aligned());
+    }
+
+    #[test]
+    fn checked_bytes_detects_overflow() {
+        let num_vecs = NumVectors::from(1024usize);
+        assert_eq!(
+            num_vecs.checked_bytes(NumDimensions::DIMS_384, 4),
+            Some(1024 * 384 * 4)
+        );
+        assert_eq!(
+            NumVectors::from(usize::MAX).checked_bytes(NumDimensions::from(2usize), 1),
+            None
+        );
+        assert_eq!(
+            NumVectors::from(usize::MAX / 2).checked_bytes(NumDimensions::from(1usize), 4),
+            None
+        );
+        assert_eq!(
+            NumDimensions::from(usize::MAX).checked_mul(NumVectors::from(2usize)),
+            None
+        );
+    }
+}
@@ -1,53 +1,214 @@
+use crate::chunk_storage::ChunkStorage;
+#[cfg(unix)]
+use crate::chunk_storage::MappedFile;
+use crate::fixed_size_memory_chunk::{page_size, touch_pages, AccessHint, AllocationHint};
 use abstractions::{NumDimensions, NumVectors};
-use alloc_madvise::Memory;
+use alloc_madvise::{AllocationError as AllocError, Memory};
+use rayon::prelude::*;
+use std::fmt::{Debug, Display, Formatter};
+use std::io::{ErrorKind, Read, Write};
+#[cfg(unix)]
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The edge length of the square tiles used by [`AnySizeMemoryChunk::as_transposed_parallel`].
+pub const TRANSPOSE_TILE_SIZE: usize = 64;
+
+/// The size of a transparent huge page; allocations of a multiple of this size
+/// are advised to use huge pages by the allocator.
+const HUGE_PAGE_SIZE: usize = 2 * 1024 * 1024;
+
+/// The arrangement of the payload of an [`AnySizeMemoryChunk`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Layout {
+    /// The payload holds the `num_vecs × num_dims` matrix of consecutive vectors.
+    RowMajor,
+    /// The payload holds the `num_dims × num_vecs` matrix of consecutive dimensions,
+    /// e.g. after [`AnySizeMemoryChunk::transpose_in_place`].
+    ColumnMajor,
+}
 
-#[derive(Debug)]
 pub struct AnySizeMemoryChunk {
     num_vecs: usize,
     virt_num_vecs: usize,
     num_dims: usize,
-    data: Memory,
+    data: ChunkStorage,
+    locked: AtomicBool,
+    layout: Layout,
 }
 
 impl AnySizeMemoryChunk {
+    /// The version of the format written by [`AnySizeMemoryChunk::to_writer`].
+    const SERIALIZATION_VERSION: u32 = 1;
+
+    /// The version of the legacy big-endian format without a byte order mark.
+    const LEGACY_SERIALIZATION_VERSION: u32 = 0;
+
+    /// Stored in place of the legacy format's padding to identify the byte order.
+    const BYTE_ORDER_MARK: u32 = 0x0102_0304;
+
+    /// The size of the serialization header in bytes.
+    const SERIALIZATION_HEADER_SIZE: usize = 16;
+
+    /// Allocates a zeroed chunk for the specified number of vectors.
+    ///
+    /// Any number of dimensions is supported; SIMD dot products fall back to
+    /// a scalar implementation if it is not a multiple of their register width.
+    ///
+    /// ## Panics
+    /// Panics if the memory could not be allocated; see [`AnySizeMemoryChunk::try_new`]
+    /// for a fallible alternative.
     pub fn new(num_vectors: NumVectors, num_dimensions: NumDimensions) -> Self {
-        assert_eq!(
-            *num_dimensions % 16,
-            0,
-            "Number of dimensions must be a multiple of 16"
-        );
+        Self::try_new(num_vectors, num_dimensions).expect("memory allocation failed")
+    }
 
-        let num_elems = num_vectors * num_dimensions;
-        let num_bytes = num_elems * std::mem::size_of::<f32>();
-        let chunk = Memory::allocate(num_bytes, false, true).expect("memory allocation failed");
+    /// Allocates a zeroed chunk for the specified number of vectors,
+    /// returning an error if the memory could not be allocated.
+    pub fn try_new(
+        num_vectors: NumVectors,
+        num_dimensions: NumDimensions,
+    ) -> Result<Self, ChunkError> {
+        Self::try_new_with_hints(
+            num_vectors,
+            num_dimensions,
+            AccessHint::Random,
+            AllocationHint::Default,
+        )
+    }
 
-        Self {
-            data: chunk,
+    /// Allocates a zeroed chunk for the specified number of vectors with the specified
+    /// access pattern and page placement, returning an error if the memory could not be allocated.
+    pub fn try_new_with_hints(
+        num_vectors: NumVectors,
+        num_dimensions: NumDimensions,
+        access_pattern: AccessHint,
+        placement: AllocationHint,
+    ) -> Result<Self, ChunkError> {
+        Self::allocate(
+            num_vectors,
+            num_dimensions,
+            access_pattern,
+            placement,
+            false,
+        )
+    }
+
+    /// Allocates a zeroed chunk, optionally rounding the allocation up to a multiple
+    /// of [`HUGE_PAGE_SIZE`] so that it is backed by transparent huge pages on Linux.
+    fn allocate(
+        num_vectors: NumVectors,
+        num_dimensions: NumDimensions,
+        access_pattern: AccessHint,
+        placement: AllocationHint,
+        huge_pages: bool,
+    ) -> Result<Self, ChunkError> {
+        let mut num_bytes = checked_num_bytes::<f32>(num_vectors, num_dimensions)?;
+        if huge_pages && cfg!(target_os = "linux") && num_bytes > 0 {
+            num_bytes = num_bytes
+                .checked_add(HUGE_PAGE_SIZE - 1)
+                .map(|n| n / HUGE_PAGE_SIZE * HUGE_PAGE_SIZE)
+                .ok_or(ChunkError::SizeOverflow)?;
+        }
+
+        let sequential = access_pattern == AccessHint::Seqential;
+        let mut chunk = Memory::allocate(num_bytes, sequential, true)?;
+        if placement == AllocationHint::FirstTouch {
+            touch_pages(&mut chunk);
+        }
+
+        Ok(Self {
+            data: chunk.into(),
             num_vecs: *num_vectors,
             virt_num_vecs: *num_vectors,
             num_dims: *num_dimensions,
-        }
+            locked: AtomicBool::new(false),
+            layout: Layout::RowMajor,
+        })
     }
 
+    /// Creates a builder for configuring the allocation of a chunk.
+    pub fn builder() -> AnySizeMemoryChunkBuilder {
+        AnySizeMemoryChunkBuilder::default()
+    }
+
+    /// Allocates a zeroed chunk whose number of vectors is rounded up to a multiple
+    /// of `alignment`, e.g. the work group size of a kernel.
+    ///
+    /// The padding consists of zero vectors that are part of the vectors in use,
+    /// so they score zero in dot products. Use [`AnySizeMemoryChunk::truncate_to_multiple_of`]
+    /// to drop the trailing vectors instead.
+    ///
+    /// ## Panics
+    /// Panics if `alignment` is zero or if the memory could not be allocated.
+    pub fn new_aligned_vecs(
+        num_vectors: NumVectors,
+        num_dimensions: NumDimensions,
+        alignment: usize,
+        access_hint: AccessHint,
+    ) -> Self {
+        assert_ne!(alignment, 0, "alignment must be positive");
+        Self::builder()
+            .num_vectors(num_vectors)
+            .num_dimensions(num_dimensions)
+            .access_hint(access_hint)
+            .vec_alignment(alignment)
+            .build()
+            .expect("memory allocation failed")
+    }
+
+    /// Reduces the number of vectors in use to the largest multiple of `alignment`
+    /// not exceeding it, dropping the trailing vectors.
+    ///
+    /// If fewer than `alignment` vectors are in use, all of them are dropped.
+    ///
+    /// ## Panics
+    /// Panics if `alignment` is zero or the chunk is column-major.
+    pub fn truncate_to_multiple_of(&mut self, alignment: usize) {
+        assert_ne!(alignment, 0, "alignment must be positive");
+        self.assert_row_major("truncating it");
+        self.virt_num_vecs -= self.virt_num_vecs % alignment;
+    }
+
+    /// Sets the number of vectors in use; zero uses all allocated vectors.
+    ///
+    /// ## Panics
+    /// Panics if the chunk is column-major, since the columns would be scrambled.
     pub fn use_num_vecs(&mut self, num_vecs: NumVectors) {
+        self.assert_row_major("changing the number of vectors in use");
         self.virt_num_vecs = match *num_vecs {
             0 => self.num_vecs,
-            x => x.min(self.data.len()),
+            x => x.min(self.num_vecs),
         }
     }
 
     pub fn get_vec(&self, idx: usize) -> &[f32] {
         let start = idx * self.num_dims;
         let end = (idx + 1) * self.num_dims;
-        debug_assert!(idx < self.data.len());
+        debug_assert!(idx < self.virt_num_vecs);
+        debug_assert_eq!(self.layout, Layout::RowMajor, "chunk is column-major");
         let data: &[f32] = self.data.as_ref();
         &data[start..end]
     }
 
+    pub fn get_vec_mut(&mut self, idx: usize) -> &mut [f32] {
+        let start = idx * self.num_dims;
+        let end = (idx + 1) * self.num_dims;
+        debug_assert!(idx < self.virt_num_vecs);
+        debug_assert_eq!(self.layout, Layout::RowMajor, "chunk is column-major");
+        let data: &mut [f32] = self.data.as_mut();
+        &mut data[start..end]
+    }
+
     pub fn len(&self) -> usize {
         self.num_dims * self.virt_num_vecs
     }
 
+    /// The number of allocated bytes.
+    fn num_bytes(&self) -> usize {
+        checked_num_bytes::<f32>(self.num_vecs.into(), self.num_dims.into())
+            .expect("allocated chunk size overflows")
+    }
+
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
@@ -60,40 +221,1160 @@ impl AnySizeMemoryChunk {
         NumDimensions::from(self.num_dims)
     }
 
+    /// The current arrangement of the payload.
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Returns the values of the specified dimension of all vectors in use
+    /// of a column-major chunk.
+    pub fn get_col_major_vec(&self, dim: usize) -> &[f32] {
+        debug_assert!(dim < self.num_dims);
+        debug_assert_eq!(self.layout, Layout::ColumnMajor, "chunk is row-major");
+        &self.as_slice()[dim * self.virt_num_vecs..(dim + 1) * self.virt_num_vecs]
+    }
+
+    /// Returns the logical payload, i.e. the data of all used vectors.
+    pub fn as_slice(&self) -> &[f32] {
+        let data: &[f32] = self.data.as_ref();
+        &data[..self.num_dims * self.virt_num_vecs]
+    }
+
+    /// Returns the logical payload, i.e. the data of all used vectors.
+    pub fn as_mut_slice(&mut self) -> &mut [f32] {
+        let data: &mut [f32] = self.data.as_mut();
+        &mut data[..self.num_dims * self.virt_num_vecs]
+    }
+
+    /// Iterates the vectors in use in order.
+    pub fn rows(&self) -> impl ExactSizeIterator<Item = &[f32]> {
+        debug_assert_eq!(self.layout, Layout::RowMajor, "chunk is column-major");
+        self.as_slice().chunks_exact(self.num_dims)
+    }
+
+    /// Iterates the vectors in use in order, mutably.
+    pub fn rows_mut(&mut self) -> impl ExactSizeIterator<Item = &mut [f32]> {
+        debug_assert_eq!(self.layout, Layout::RowMajor, "chunk is column-major");
+        let num_dims = self.num_dims;
+        self.as_mut_slice().chunks_exact_mut(num_dims)
+    }
+
+    /// Iterates the vectors in use in parallel.
+    pub fn par_rows(&self) -> impl IndexedParallelIterator<Item = &[f32]> {
+        debug_assert_eq!(self.layout, Layout::RowMajor, "chunk is column-major");
+        self.as_slice().par_chunks_exact(self.num_dims)
+    }
+
+    /// Iterates the vectors in use in parallel, mutably.
+    pub fn par_rows_mut(&mut self) -> impl IndexedParallelIterator<Item = &mut [f32]> {
+        debug_assert_eq!(self.layout, Layout::RowMajor, "chunk is column-major");
+        let num_dims = self.num_dims;
+        self.as_mut_slice().par_chunks_exact_mut(num_dims)
+    }
+
+    /// Scales every vector in use to unit L2 norm in place.
+    ///
+    /// Vectors with a norm of zero are left unchanged.
+    pub fn normalize_rows(&mut self) {
+        self.rows_mut().for_each(normalize_row);
+    }
+
+    /// Scales every vector in use to unit L2 norm in place, processing the vectors in parallel.
+    ///
+    /// Vectors with a norm of zero are left unchanged.
+    pub fn par_normalize_rows(&mut self) {
+        self.par_rows_mut().for_each(normalize_row);
+    }
+
+    /// Returns the logical payload as bytes, e.g. for writing it to a file or socket.
+    ///
+    /// The values are in the host's byte order, which matches the little-endian
+    /// vector database payload only on little-endian hosts.
+    pub fn as_bytes(&self) -> &[u8] {
+        let values = self.as_slice();
+        // SAFETY: The bytes cover exactly the values, and u8 has no alignment requirement.
+        unsafe { std::slice::from_raw_parts(values.as_ptr() as *const u8, values.len() * 4) }
+    }
+
+    /// Returns the logical payload as mutable bytes, e.g. for reading it from a file or socket.
+    ///
+    /// The bytes are interpreted in the host's byte order; see [`AnySizeMemoryChunk::as_bytes`].
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        let values = self.as_mut_slice();
+        // SAFETY: The bytes cover exactly the values, u8 has no alignment requirement,
+        // and every bit pattern is a valid f32.
+        unsafe { std::slice::from_raw_parts_mut(values.as_mut_ptr() as *mut u8, values.len() * 4) }
+    }
+
+    /// Copies the logical payload into an owned vector.
+    pub fn into_vec(self) -> Vec<f32> {
+        Vec::from(self.as_slice())
+    }
+
+    /// Writes the vectors in use to the writer.
+    ///
+    /// The format mirrors the vector database files: a 16 byte header consisting of the
+    /// format version, a byte order mark, the number of vectors and the number of dimensions
+    /// as little-endian `u32` values, followed by the little-endian `f32` payload in row-major
+    /// order. Such files can be mapped using [`AnySizeMemoryChunk::mmap_from_path`].
+    ///
+    /// Fails with [`ErrorKind::InvalidInput`] if the chunk is column-major or the number
+    /// of vectors or dimensions does not fit the header.
+    pub fn to_writer<W: Write>(&self, mut writer: W) -> Result<(), std::io::Error> {
+        if self.layout != Layout::RowMajor {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                "column-major chunks cannot be serialized",
+            ));
+        }
+
+        let too_large = |_| {
+            std::io::Error::new(
+                ErrorKind::InvalidInput,
+                "the chunk is too large to be serialized",
+            )
+        };
+        let num_vecs = u32::try_from(self.virt_num_vecs).map_err(too_large)?;
+        let num_dims = u32::try_from(self.num_dims).map_err(too_large)?;
+
+        writer.write_all(&Self::SERIALIZATION_VERSION.to_le_bytes())?;
+        writer.write_all(&Self::BYTE_ORDER_MARK.to_le_bytes())?;
+        writer.write_all(&num_vecs.to_le_bytes())?;
+        writer.write_all(&num_dims.to_le_bytes())?;
+
+        if cfg!(target_endian = "little") {
+            writer.write_all(self.as_bytes())?;
+            return writer.flush();
+        }
+
+        let mut buffer = Vec::with_capacity(self.num_dims * std::mem::size_of::<f32>());
+        for vec in self.as_slice().chunks_exact(self.num_dims) {
+            buffer.clear();
+            buffer.extend(vec.iter().flat_map(|value| value.to_le_bytes()));
+            writer.write_all(&buffer)?;
+        }
+
+        writer.flush()
+    }
+
+    /// Reads a chunk written by [`AnySizeMemoryChunk::to_writer`] into freshly allocated memory.
+    ///
+    /// Chunks of the legacy big-endian version 0 are converted while reading.
+    pub fn from_reader<R: Read>(
+        mut reader: R,
+        access_pattern: AccessHint,
+    ) -> Result<Self, std::io::Error> {
+        let mut header = [0u8; Self::SERIALIZATION_HEADER_SIZE];
+        reader.read_exact(&mut header)?;
+        let (little_endian, num_vecs, num_dims) = Self::parse_header(&header)?;
+
+        // The allocator rejects empty allocations, but chunks without vectors are valid.
+        if num_vecs == 0 {
+            return Ok(Self {
+                data: Memory::default().into(),
+                num_vecs,
+                virt_num_vecs: num_vecs,
+                num_dims,
+                locked: AtomicBool::new(false),
+                layout: Layout::RowMajor,
+            });
+        }
+
+        let mut chunk = Self::try_new_with_hints(
+            num_vecs.into(),
+            num_dims.into(),
+            access_pattern,
+            AllocationHint::Default,
+        )
+        .map_err(|e| std::io::Error::new(ErrorKind::OutOfMemory, e))?;
+
+        let mut buffer = vec![0u8; num_dims * std::mem::size_of::<f32>()];
+        for vec in chunk.as_mut_slice().chunks_exact_mut(num_dims) {
+            reader.read_exact(&mut buffer)?;
+            for (value, bytes) in vec.iter_mut().zip(buffer.chunks_exact(4)) {
+                let bytes = bytes.try_into().expect("slice is 4 bytes");
+                *value = if little_endian {
+                    f32::from_le_bytes(bytes)
+                } else {
+                    f32::from_be_bytes(bytes)
+                };
+            }
+        }
+
+        Ok(chunk)
+    }
+
+    /// Maps a chunk file written by [`AnySizeMemoryChunk::to_writer`] into memory,
+    /// using the file's payload as the chunk's memory without copying or converting it.
+    ///
+    /// The mapping is private: pages are read from the file when first accessed, and
+    /// modifications of the chunk are never written back to the file. The file must not
+    /// be truncated while it is mapped. Reallocating operations such as
+    /// [`AnySizeMemoryChunk::reserve`] copy the payload into allocated memory.
+    ///
+    /// Fails with [`ErrorKind::InvalidData`] for legacy big-endian chunks, which need to be
+    /// converted using [`AnySizeMemoryChunk::from_reader`], and with [`ErrorKind::Unsupported`]
+    /// on big-endian hosts.
+    #[cfg(unix)]
+    pub fn mmap_from_path<P: AsRef<Path>>(
+        path: P,
+        access_pattern: AccessHint,
+    ) -> Result<Self, std::io::Error> {
+        if cfg!(target_endian = "big") {
+            return Err(std::io::Error::new(
+                ErrorKind::Unsupported,
+                "chunks can only be mapped on little-endian hosts",
+            ));
+        }
+
+        let mut file = std::fs::File::open(path)?;
+        let mut header = [0u8; Self::SERIALIZATION_HEADER_SIZE];
+        file.read_exact(&mut header)?;
+        let (little_endian, num_vecs, num_dims) = Self::parse_header(&header)?;
+        if !little_endian {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                "big-endian chunks cannot be mapped",
+            ));
+        }
+
+        let too_large = || std::io::Error::new(ErrorKind::InvalidData, "chunk is too large");
+        let num_elems = num_vecs.checked_mul(num_dims).ok_or_else(too_large)?;
+        let num_bytes = num_elems
+            .checked_mul(std::mem::size_of::<f32>())
+            .and_then(|num_bytes| num_bytes.checked_add(Self::SERIALIZATION_HEADER_SIZE))
+            .ok_or_else(too_large)?;
+        if file.metadata()?.len() < num_bytes as u64 {
+            return Err(std::io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "chunk file is truncated",
+            ));
+        }
+
+        let sequential = access_pattern == AccessHint::Seqential;
+        // SAFETY: The file length was checked above, and the header size is a multiple of four.
+        let mapped = unsafe {
+            MappedFile::map(
+                &file,
+                Self::SERIALIZATION_HEADER_SIZE,
+                num_elems,
+                sequential,
+            )?
+        };
+
+        Ok(Self {
+            data: ChunkStorage::Mapped(mapped),
+            num_vecs,
+            virt_num_vecs: num_vecs,
+            num_dims,
+            locked: AtomicBool::new(false),
+            layout: Layout::RowMajor,
+        })
+    }
+
+    /// Parses a serialization header into the byte order (`true` for little-endian),
+    /// the number of vectors and the number of dimensions.
+    fn parse_header(
+        header: &[u8; Self::SERIALIZATION_HEADER_SIZE],
+    ) -> Result<(bool, usize, usize), std::io::Error> {
+        let field = |i: usize| -> [u8; 4] {
+            header[4 * i..4 * (i + 1)]
+                .try_into()
+                .expect("field has exactly four bytes")
+        };
+
+        let (little_endian, expected_version) = match field(1) {
+            mark if mark == Self::BYTE_ORDER_MARK.to_le_bytes() => {
+                (true, Self::SERIALIZATION_VERSION)
+            }
+            mark if mark == Self::BYTE_ORDER_MARK.to_be_bytes() => {
+                (false, Self::SERIALIZATION_VERSION)
+            }
+            padding if padding == u32::MAX.to_be_bytes() => {
+                (false, Self::LEGACY_SERIALIZATION_VERSION)
+            }
+            _ => {
+                return Err(std::io::Error::new(
+                    ErrorKind::InvalidData,
+                    "unrecognized chunk byte order mark",
+                ))
+            }
+        };
+
+        let read_u32 = |i: usize| {
+            if little_endian {
+                u32::from_le_bytes(field(i))
+            } else {
+                u32::from_be_bytes(field(i))
+            }
+        };
+
+        let version = read_u32(0);
+        if version != expected_version {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("unsupported chunk version {version}"),
+            ));
+        }
+
+        let num_vecs = read_u32(2) as usize;
+        let num_dims = read_u32(3) as usize;
+        if num_dims == 0 {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                "number of dimensions must be positive",
+            ));
+        }
+
+        Ok((little_endian, num_vecs, num_dims))
+    }
+
+    /// Returns the transposed (column-major) data of a row-major chunk.
     pub fn as_transposed(&self) -> Vec<f32> {
+        debug_assert_eq!(self.layout, Layout::RowMajor, "chunk is column-major");
         let mut vec = Vec::from(self.as_ref());
         transpose::transpose(self.as_ref(), &mut vec, self.num_dims, self.virt_num_vecs);
         vec
     }
 
+    /// Transposes the vectors in use within the existing allocation, flipping the
+    /// [`Layout`] between row-major and column-major.
+    ///
+    /// Unlike [`AnySizeMemoryChunk::as_transposed`], this does not require a second matrix;
+    /// the cycle-following algorithm only needs scratch space for `max(num_vecs, num_dims)` values.
+    /// While the chunk is column-major, the accessors of individual vectors must not be used;
+    /// see [`AnySizeMemoryChunk::get_col_major_vec`]. Operations that change the number
+    /// of vectors or serialize the chunk reject column-major chunks.
+    pub fn transpose_in_place(&mut self) {
+        let (width, height) = match self.layout {
+            Layout::RowMajor => (self.num_dims, self.virt_num_vecs),
+            Layout::ColumnMajor => (self.virt_num_vecs, self.num_dims),
+        };
+
+        if width > 0 && height > 0 {
+            let mut scratch = vec![0.0; width.max(height)];
+            transpose::transpose_inplace(self.as_mut_slice(), &mut scratch, width, height);
+        }
+        self.layout = match self.layout {
+            Layout::RowMajor => Layout::ColumnMajor,
+            Layout::ColumnMajor => Layout::RowMajor,
+        };
+    }
+
+    /// Returns the transposed (column-major) data, like [`AnySizeMemoryChunk::as_transposed`],
+    /// but processes bands of [`TRANSPOSE_TILE_SIZE`] output rows in parallel
+    /// using cache-friendly square tiles.
+    pub fn as_transposed_parallel(&self) -> Vec<f32> {
+        debug_assert_eq!(self.layout, Layout::RowMajor, "chunk is column-major");
+        let num_dims = self.num_dims;
+        let num_vecs = self.virt_num_vecs;
+        let src = self.as_slice();
+
+        let mut vec = vec![0.0; src.len()];
+        if vec.is_empty() {
+            return vec;
+        }
+
+        vec.par_chunks_mut(TRANSPOSE_TILE_SIZE * num_vecs)
+            .enumerate()
+            .for_each(|(band, dest)| {
+                let first_dim = band * TRANSPOSE_TILE_SIZE;
+                let band_dims = dest.len() / num_vecs;
+                for first_vec in (0..num_vecs).step_by(TRANSPOSE_TILE_SIZE) {
+                    let tile_vecs = TRANSPOSE_TILE_SIZE.min(num_vecs - first_vec);
+                    for v in first_vec..first_vec + tile_vecs {
+                        let row = &src[v * num_dims + first_dim..][..band_dims];
+                        for (d, &value) in row.iter().enumerate() {
+                            dest[d * num_vecs + v] = value;
+                        }
+                    }
+                }
+            });
+
+        vec
+    }
+
+    /// Replicates the vectors in use twice; see [`AnySizeMemoryChunk::replicate`].
     pub fn double(&mut self) {
-        self.num_vecs *= 2;
-        self.virt_num_vecs *= 2;
+        self.replicate(2);
+    }
+
+    /// Grows the chunk to `factor` times the number of vectors in use
+    /// by copying the vectors in use `factor` times.
+    ///
+    /// This is a benchmarking aid to build large data sets from a small seed;
+    /// the copies are identical vectors. A chunk without vectors in use is left unchanged.
+    ///
+    /// ## Panics
+    /// Panics if `factor` is zero, the chunk is column-major, the size of the replicated
+    /// chunk overflows or its memory could not be allocated.
+    pub fn replicate(&mut self, factor: usize) {
+        assert!(factor > 0, "replication factor must be positive");
+        self.assert_row_major("replicating it");
+        if self.is_empty() {
+            return;
+        }
 
-        let num_elems = self.num_dims * self.num_vecs;
-        let num_bytes = num_elems * std::mem::size_of::<f32>();
+        let num_vecs = self
+            .virt_num_vecs
+            .checked_mul(factor)
+            .expect("replicated chunk size overflows");
+        let num_bytes = checked_num_bytes::<f32>(num_vecs.into(), self.num_dims.into())
+            .expect("replicated chunk size overflows");
         let mut chunk =
             Memory::allocate(num_bytes, false, false).expect("memory allocation failed");
 
-        let src: &[f32] = self.data.as_ref();
+        let src = self.as_slice();
+        let dest: &mut [f32] = chunk.as_mut();
+        for copy in dest.chunks_exact_mut(src.len()) {
+            copy.copy_from_slice(src);
+        }
+
+        self.unlock_memory();
+        self.data = chunk.into();
+        self.num_vecs = num_vecs;
+        self.virt_num_vecs = num_vecs;
+    }
+
+    /// Reallocates the backing memory to hold exactly the vectors currently in use,
+    /// releasing the memory of vectors trimmed by [`AnySizeMemoryChunk::use_num_vecs`].
+    ///
+    /// ## Panics
+    /// Panics if the chunk is column-major.
+    pub fn shrink_to_fit(&mut self) {
+        self.assert_row_major("shrinking it");
+        if self.virt_num_vecs == self.num_vecs {
+            return;
+        }
+
+        let num_elems = self.num_dims * self.virt_num_vecs;
+        let chunk = if num_elems == 0 {
+            Memory::default()
+        } else {
+            let num_bytes =
+                checked_num_bytes::<f32>(self.virt_num_vecs.into(), self.num_dims.into())
+                    .expect("chunk size overflows");
+            let mut chunk =
+                Memory::allocate(num_bytes, false, false).expect("memory allocation failed");
+            let dest: &mut [f32] = chunk.as_mut();
+            dest.copy_from_slice(self.as_slice());
+            chunk
+        };
+
+        self.unlock_memory();
+        self.data = chunk.into();
+        self.num_vecs = self.virt_num_vecs;
+    }
+
+    /// Grows the backing memory to hold `additional` more vectors, which are zeroed.
+    ///
+    /// The number of vectors in use is not changed; use [`AnySizeMemoryChunk::use_num_vecs`]
+    /// to make the reserved vectors available.
+    ///
+    /// ## Panics
+    /// Panics if the chunk is column-major, the new size overflows
+    /// or the memory could not be allocated.
+    pub fn reserve(&mut self, additional: NumVectors) {
+        self.assert_row_major("reserving vectors");
+        if *additional == 0 {
+            return;
+        }
+
+        let num_vecs = self
+            .num_vecs
+            .checked_add(*additional)
+            .expect("reserved chunk size overflows");
+        let num_bytes = checked_num_bytes::<f32>(num_vecs.into(), self.num_dims.into())
+            .expect("reserved chunk size overflows");
+        let mut chunk = Memory::allocate(num_bytes, false, true).expect("memory allocation failed");
+
+        let data: &[f32] = self.data.as_ref();
+        let src = &data[..self.num_vecs * self.num_dims];
         let dest: &mut [f32] = chunk.as_mut();
         dest[..src.len()].copy_from_slice(src);
-        dest[src.len()..].copy_from_slice(src);
 
-        self.data = chunk;
+        self.unlock_memory();
+        self.data = chunk.into();
+        self.num_vecs = num_vecs;
+    }
+
+    /// Faults in the pages of all vectors in use by reading one value per page,
+    /// moving the page fault cost out of the first search.
+    pub fn prefetch(&self) {
+        self.prefetch_range(0, self.virt_num_vecs);
+    }
+
+    /// Faults in the pages of the vectors `start_vec..end_vec` by reading one value per page.
+    pub fn prefetch_range(&self, start_vec: usize, end_vec: usize) {
+        debug_assert!(start_vec <= end_vec);
+        debug_assert!(end_vec <= self.virt_num_vecs);
+
+        let data = &self.as_slice()[start_vec * self.num_dims..end_vec * self.num_dims];
+        let stride = (page_size() / std::mem::size_of::<f32>()).max(1);
+        for value in data.iter().step_by(stride) {
+            // SAFETY: The reference is valid; the volatile read keeps the load from being elided.
+            unsafe { std::ptr::read_volatile(value) };
+        }
+    }
+
+    /// Locks the backing memory into RAM using `mlock`, preventing it from being swapped out.
+    ///
+    /// The memory is unlocked again when the chunk is dropped. Reallocating operations
+    /// such as [`AnySizeMemoryChunk::double`] release the lock and need to lock again.
+    ///
+    /// Locking is subject to the `RLIMIT_MEMLOCK` resource limit (see `ulimit -l`),
+    /// which is usually small for unprivileged processes; locking larger chunks may
+    /// require raising the limit or the `CAP_IPC_LOCK` capability.
+    #[cfg(unix)]
+    pub fn lock_memory(&self) -> Result<(), std::io::Error> {
+        if self.locked.load(Ordering::Acquire) {
+            return Ok(());
+        }
+
+        let result = unsafe { libc::mlock(self.data.as_ptr(), self.data.len()) };
+        if result != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        self.locked.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// Determines whether the backing memory is locked into RAM.
+    pub fn is_memory_locked(&self) -> bool {
+        self.locked.load(Ordering::Acquire)
+    }
+
+    /// Rejects operations that would scramble the columns of a column-major chunk.
+    fn assert_row_major(&self, operation: &str) {
+        assert!(
+            self.layout == Layout::RowMajor,
+            "chunk is column-major; transpose it back before {operation}"
+        );
+    }
+
+    fn unlock_memory(&self) {
+        #[cfg(unix)]
+        if self.locked.swap(false, Ordering::AcqRel) {
+            unsafe { libc::munlock(self.data.as_ptr(), self.data.len()) };
+        }
+    }
+}
+
+/// Prints the shape of the chunk, but not its payload.
+impl Debug for AnySizeMemoryChunk {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AnySizeMemoryChunk")
+            .field("num_vecs", &self.num_vecs)
+            .field("virt_num_vecs", &self.virt_num_vecs)
+            .field("num_dims", &self.num_dims)
+            .field("bytes", &self.num_bytes())
+            .field("locked", &self.is_memory_locked())
+            .field("layout", &self.layout)
+            .finish()
+    }
+}
+
+impl Display for AnySizeMemoryChunk {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} vectors of {} dimensions ({} allocated, {} bytes)",
+            self.virt_num_vecs,
+            self.num_dims,
+            self.num_vecs,
+            self.num_bytes()
+        )
+    }
+}
+
+impl Drop for AnySizeMemoryChunk {
+    fn drop(&mut self) {
+        self.unlock_memory();
+    }
+}
+
+/// Configures the allocation of an [`AnySizeMemoryChunk`].
+///
+/// By default, chunks are allocated for random access with the default page placement
+/// and without rounding up the number of vectors.
+#[derive(Debug, Copy, Clone)]
+pub struct AnySizeMemoryChunkBuilder {
+    num_vectors: NumVectors,
+    num_dimensions: NumDimensions,
+    access_hint: AccessHint,
+    allocation_hint: AllocationHint,
+    vec_alignment: usize,
+    huge_pages: bool,
+}
+
+impl Default for AnySizeMemoryChunkBuilder {
+    fn default() -> Self {
+        Self {
+            num_vectors: NumVectors::default(),
+            num_dimensions: NumDimensions::default(),
+            access_hint: AccessHint::Random,
+            allocation_hint: AllocationHint::Default,
+            vec_alignment: 1,
+            huge_pages: false,
+        }
+    }
+}
+
+impl AnySizeMemoryChunkBuilder {
+    pub fn num_vectors(mut self, num_vectors: NumVectors) -> Self {
+        self.num_vectors = num_vectors;
+        self
+    }
+
+    pub fn num_dimensions(mut self, num_dimensions: NumDimensions) -> Self {
+        self.num_dimensions = num_dimensions;
+        self
+    }
+
+    pub fn access_hint(mut self, access_hint: AccessHint) -> Self {
+        self.access_hint = access_hint;
+        self
+    }
+
+    pub fn allocation_hint(mut self, allocation_hint: AllocationHint) -> Self {
+        self.allocation_hint = allocation_hint;
+        self
+    }
+
+    /// Rounds the number of vectors up to a multiple of `alignment`;
+    /// see [`AnySizeMemoryChunk::new_aligned_vecs`].
+    pub fn vec_alignment(mut self, alignment: usize) -> Self {
+        self.vec_alignment = alignment;
+        self
+    }
+
+    /// Requests transparent huge pages for the chunk to reduce TLB misses
+    /// when scanning large chunks.
+    ///
+    /// On Linux, the allocation is rounded up to a multiple of 2 MiB, which makes
+    /// the allocator align it accordingly and advise `MADV_HUGEPAGE`. Whether huge pages
+    /// are actually used depends on the system's configuration. On other platforms,
+    /// this has no effect.
+    pub fn huge_pages(mut self, huge_pages: bool) -> Self {
+        self.huge_pages = huge_pages;
+        self
+    }
+
+    /// Allocates the zeroed chunk.
+    pub fn build(self) -> Result<AnySizeMemoryChunk, ChunkError> {
+        if self.vec_alignment == 0 {
+            return Err(ChunkError::ZeroAlignment);
+        }
+
+        let num_vecs = (*self.num_vectors)
+            .checked_add(self.vec_alignment - 1)
+            .map(|n| n / self.vec_alignment * self.vec_alignment)
+            .ok_or(ChunkError::SizeOverflow)?;
+        AnySizeMemoryChunk::allocate(
+            num_vecs.into(),
+            self.num_dimensions,
+            self.access_hint,
+            self.allocation_hint,
+            self.huge_pages,
+        )
+    }
+}
+
+/// Error returned by [`AnySizeMemoryChunkBuilder::build`] and the fallible constructors.
+#[derive(Debug, PartialEq)]
+pub enum ChunkError {
+    /// The vector alignment is zero.
+    ZeroAlignment,
+    /// The size of the chunk in bytes overflows.
+    SizeOverflow,
+    /// The memory could not be allocated.
+    Alloc(AllocError),
+}
+
+impl Display for ChunkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChunkError::ZeroAlignment => write!(f, "the vector alignment must be positive"),
+            ChunkError::SizeOverflow => write!(f, "the chunk size overflows"),
+            ChunkError::Alloc(e) => write!(f, "failed to allocate the chunk: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ChunkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ChunkError::ZeroAlignment | ChunkError::SizeOverflow => None,
+            ChunkError::Alloc(e) => Some(e),
+        }
+    }
+}
+
+impl From<AllocError> for ChunkError {
+    fn from(value: AllocError) -> Self {
+        ChunkError::Alloc(value)
+    }
+}
+
+/// Calculates the number of bytes required to store the vectors,
+/// reporting an arithmetic overflow as [`ChunkError::SizeOverflow`].
+pub(crate) fn checked_num_bytes<T>(
+    num_vectors: NumVectors,
+    num_dimensions: NumDimensions,
+) -> Result<usize, ChunkError> {
+    num_vectors
+        .checked_bytes(num_dimensions, std::mem::size_of::<T>())
+        .filter(|&num_bytes| num_bytes <= isize::MAX as usize)
+        .ok_or(ChunkError::SizeOverflow)
+}
+
+/// Scales the vector to unit L2 norm unless its norm is zero.
+fn normalize_row(row: &mut [f32]) {
+    let norm_sq: f32 = row.iter().map(|x| x * x).sum();
+    if norm_sq == 0.0 {
+        return;
+    }
+
+    let inv_norm = 1.0 / norm_sq.sqrt();
+    for x in row.iter_mut() {
+        *x *= inv_norm;
     }
 }
 
 impl AsRef<[f32]> for AnySizeMemoryChunk {
     fn as_ref(&self) -> &[f32] {
-        let data: &[f32] = self.data.as_ref();
-        &data[..self.num_dims * self.virt_num_vecs]
+        self.as_slice()
     }
 }
 
 impl AsMut<[f32]> for AnySizeMemoryChunk {
     fn as_mut(&mut self) -> &mut [f32] {
-        let data: &mut [f32] = self.data.as_mut();
-        &mut data[..self.num_dims * self.virt_num_vecs]
+        self.as_mut_slice()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_works() {
+        let chunk = AnySizeMemoryChunk::builder()
+            .num_vectors(NumVectors::from(10))
+            .num_dimensions(NumDimensions::from(16))
+            .access_hint(AccessHint::Seqential)
+            .vec_alignment(8)
+            .build()
+            .unwrap();
+        assert_eq!(*chunk.num_vecs(), 16);
+        assert_eq!(*chunk.num_dims(), 16);
+
+        let result = AnySizeMemoryChunk::builder()
+            .num_vectors(NumVectors::from(10))
+            .num_dimensions(NumDimensions::from(16))
+            .vec_alignment(0)
+            .build();
+        assert_eq!(result.unwrap_err(), ChunkError::ZeroAlignment);
+
+        let result = AnySizeMemoryChunk::builder()
+            .num_dimensions(NumDimensions::from(16))
+            .build();
+        assert_eq!(
+            result.unwrap_err(),
+            ChunkError::Alloc(AllocError::EmptyAllocation)
+        );
+    }
+
+    #[test]
+    fn huge_page_chunks_work() {
+        let mut chunk = AnySizeMemoryChunk::builder()
+            .num_vectors(NumVectors::from(10))
+            .num_dimensions(NumDimensions::from(16))
+            .huge_pages(true)
+            .build()
+            .unwrap();
+        assert_eq!(*chunk.num_vecs(), 10);
+        assert_eq!(chunk.as_slice().len(), 160);
+
+        #[cfg(target_os = "linux")]
+        assert_eq!(chunk.data.len() % HUGE_PAGE_SIZE, 0);
+
+        chunk.get_vec_mut(9).fill(1.0);
+        chunk.reserve(NumVectors::from(1));
+        chunk.use_num_vecs(NumVectors::from(11));
+        assert_eq!(chunk.get_vec(9), [1.0; 16]);
+        assert_eq!(chunk.get_vec(10), [0.0; 16]);
+    }
+
+    #[test]
+    fn try_new_reports_empty_allocation() {
+        let result = AnySizeMemoryChunk::try_new(NumVectors::from(0), NumDimensions::from(16));
+        assert_eq!(
+            result.unwrap_err(),
+            ChunkError::Alloc(AllocError::EmptyAllocation)
+        );
+    }
+
+    #[test]
+    fn as_bytes_views_logical_payload() {
+        let mut chunk = AnySizeMemoryChunk::new(NumVectors::from(4), NumDimensions::from(16));
+        chunk.use_num_vecs(NumVectors::from(2));
+        assert_eq!(chunk.as_bytes().len(), 2 * 16 * 4);
+
+        chunk.as_bytes_mut()[4..8].copy_from_slice(&1.5f32.to_ne_bytes());
+        assert_eq!(chunk.get_vec(0)[1], 1.5);
+        assert_eq!(chunk.as_bytes()[4..8], 1.5f32.to_ne_bytes());
+    }
+
+    #[test]
+    fn into_vec_returns_logical_payload() {
+        let mut chunk = AnySizeMemoryChunk::new(NumVectors::from(4), NumDimensions::from(16));
+        chunk.as_mut_slice()[16] = 1.0;
+        chunk.use_num_vecs(NumVectors::from(2));
+
+        let vec = chunk.into_vec();
+        assert_eq!(vec.len(), 32);
+        assert_eq!(vec[16], 1.0);
+    }
+
+    #[test]
+    fn rows_respect_used_vectors() {
+        let mut chunk = AnySizeMemoryChunk::new(NumVectors::from(4), NumDimensions::from(16));
+        for (v, row) in chunk.rows_mut().enumerate() {
+            row.fill(v as f32);
+        }
+        chunk.use_num_vecs(NumVectors::from(3));
+
+        assert_eq!(chunk.rows().len(), 3);
+        assert!(chunk
+            .rows()
+            .enumerate()
+            .all(|(v, row)| row == [v as f32; 16]));
+
+        chunk.par_rows_mut().for_each(|row| row[0] = -1.0);
+        assert_eq!(chunk.par_rows().filter(|row| row[0] == -1.0).count(), 3);
+    }
+
+    #[test]
+    fn normalize_rows_works() {
+        let mut chunk = AnySizeMemoryChunk::new(NumVectors::from(4), NumDimensions::from(16));
+        for (v, row) in chunk.rows_mut().enumerate() {
+            row[0] = 3.0 * v as f32;
+            row[1] = 4.0 * v as f32;
+        }
+        chunk.use_num_vecs(NumVectors::from(3));
+        let mut parallel = AnySizeMemoryChunk::new(NumVectors::from(3), NumDimensions::from(16));
+        parallel.as_mut_slice().copy_from_slice(chunk.as_slice());
+
+        chunk.normalize_rows();
+        parallel.par_normalize_rows();
+
+        assert_eq!(chunk.get_vec(0), [0.0; 16]);
+        assert_eq!(&chunk.get_vec(2)[..3], [0.6, 0.8, 0.0]);
+        assert_eq!(chunk.as_slice(), parallel.as_slice());
+
+        chunk.use_num_vecs(NumVectors::from(4));
+        assert_eq!(&chunk.get_vec(3)[..2], [9.0, 12.0]);
+    }
+
+    #[test]
+    fn vector_alignment_works() {
+        let chunk = AnySizeMemoryChunk::new_aligned_vecs(
+            NumVectors::from(33),
+            NumDimensions::from(16),
+            32,
+            AccessHint::Seqential,
+        );
+        assert_eq!(*chunk.num_vecs(), 64);
+        assert_eq!(chunk.get_vec(63), [0.0; 16]);
+
+        let mut chunk = AnySizeMemoryChunk::new(NumVectors::from(70), NumDimensions::from(16));
+        chunk.truncate_to_multiple_of(32);
+        assert_eq!(*chunk.num_vecs(), 64);
+        chunk.truncate_to_multiple_of(48);
+        assert_eq!(*chunk.num_vecs(), 48);
+    }
+
+    #[test]
+    fn formatting_omits_payload() {
+        let mut chunk = AnySizeMemoryChunk::new(NumVectors::from(4), NumDimensions::from(16));
+        chunk.use_num_vecs(NumVectors::from(3));
+
+        assert_eq!(
+            format!("{chunk:?}"),
+            "AnySizeMemoryChunk { num_vecs: 4, virt_num_vecs: 3, num_dims: 16, bytes: 256, locked: false, layout: RowMajor }"
+        );
+        assert_eq!(
+            chunk.to_string(),
+            "3 vectors of 16 dimensions (4 allocated, 256 bytes)"
+        );
+    }
+
+    #[test]
+    fn serialization_roundtrip_works() {
+        let mut chunk = AnySizeMemoryChunk::new(NumVectors::from(3), NumDimensions::from(16));
+        for (i, value) in chunk.as_mut_slice().iter_mut().enumerate() {
+            *value = i as f32 * 0.5;
+        }
+        chunk.use_num_vecs(NumVectors::from(2));
+
+        let mut buffer = Vec::new();
+        chunk.to_writer(&mut buffer).unwrap();
+        assert_eq!(buffer.len(), 16 + 2 * 16 * 4);
+
+        let restored = AnySizeMemoryChunk::from_reader(&buffer[..], AccessHint::Seqential).unwrap();
+        assert_eq!(*restored.num_vecs(), 2);
+        assert_eq!(*restored.num_dims(), 16);
+        assert_eq!(restored.as_slice(), chunk.as_slice());
+    }
+
+    #[test]
+    fn empty_chunks_roundtrip() {
+        let header: Vec<u8> = [1, AnySizeMemoryChunk::BYTE_ORDER_MARK, 0, 16]
+            .iter()
+            .flat_map(|field| field.to_le_bytes())
+            .collect();
+
+        let chunk = AnySizeMemoryChunk::from_reader(header.as_slice(), AccessHint::Random).unwrap();
+        assert_eq!(*chunk.num_vecs(), 0);
+        assert_eq!(*chunk.num_dims(), 16);
+        assert!(chunk.is_empty());
+
+        let mut buffer = Vec::new();
+        chunk.to_writer(&mut buffer).unwrap();
+        assert_eq!(buffer, header);
+    }
+
+    #[test]
+    fn legacy_big_endian_chunks_are_converted() {
+        let mut buffer = Vec::new();
+        for field in [0u32, u32::MAX, 2, 3] {
+            buffer.extend(field.to_be_bytes());
+        }
+        for value in 0..6 {
+            buffer.extend((value as f32).to_be_bytes());
+        }
+
+        let chunk = AnySizeMemoryChunk::from_reader(&buffer[..], AccessHint::Seqential).unwrap();
+        assert_eq!(*chunk.num_vecs(), 2);
+        assert_eq!(chunk.as_slice(), [0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn mmap_from_path_works() {
+        let mut chunk = AnySizeMemoryChunk::new(NumVectors::from(3), NumDimensions::from(16));
+        for (i, value) in chunk.as_mut_slice().iter_mut().enumerate() {
+            *value = i as f32 * 0.5;
+        }
+
+        let path = std::env::temp_dir().join("memchunk_mmap_from_path.bin");
+        let mut buffer = Vec::new();
+        chunk.to_writer(&mut buffer).unwrap();
+        std::fs::write(&path, &buffer).unwrap();
+
+        let mut mapped = AnySizeMemoryChunk::mmap_from_path(&path, AccessHint::Random).unwrap();
+        assert_eq!(*mapped.num_vecs(), 3);
+        assert_eq!(*mapped.num_dims(), 16);
+        assert_eq!(mapped.as_slice(), chunk.as_slice());
+
+        // Writes are private to the mapping.
+        mapped.as_mut_slice()[0] = 42.0;
+        assert_eq!(std::fs::read(&path).unwrap(), buffer);
+
+        // Reallocating copies the mapped payload.
+        mapped.reserve(NumVectors::from(1));
+        assert_eq!(mapped.as_slice()[0], 42.0);
+        assert_eq!(mapped.as_slice()[1..], chunk.as_slice()[1..]);
+
+        std::fs::write(&path, &buffer[..buffer.len() - 4]).unwrap();
+        let error = AnySizeMemoryChunk::mmap_from_path(&path, AccessHint::Random).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+
+        let mut header = buffer[..8].to_vec();
+        header.extend_from_slice(&u32::MAX.to_le_bytes());
+        header.extend_from_slice(&u32::MAX.to_le_bytes());
+        std::fs::write(&path, &header).unwrap();
+        let error = AnySizeMemoryChunk::mmap_from_path(&path, AccessHint::Random).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn parallel_transpose_works() {
+        let mut chunk = AnySizeMemoryChunk::new(NumVectors::from(100), NumDimensions::from(80));
+        for (i, value) in chunk.as_mut_slice().iter_mut().enumerate() {
+            *value = i as f32;
+        }
+        chunk.use_num_vecs(NumVectors::from(70));
+
+        let transposed = chunk.as_transposed_parallel();
+        assert_eq!(transposed, chunk.as_transposed());
+        assert_eq!(transposed[1], chunk.get_vec(1)[0]);
+        assert_eq!(transposed[70], chunk.get_vec(0)[1]);
+    }
+
+    #[test]
+    fn transpose_in_place_works() {
+        let mut chunk = AnySizeMemoryChunk::new(NumVectors::from(100), NumDimensions::from(80));
+        for (i, value) in chunk.as_mut_slice().iter_mut().enumerate() {
+            *value = i as f32;
+        }
+        chunk.use_num_vecs(NumVectors::from(70));
+        let original = Vec::from(chunk.as_slice());
+        let expected = chunk.as_transposed();
+
+        assert_eq!(chunk.layout(), Layout::RowMajor);
+        chunk.transpose_in_place();
+        assert_eq!(chunk.layout(), Layout::ColumnMajor);
+        assert_eq!(chunk.as_slice(), expected);
+        assert_eq!(chunk.get_col_major_vec(1)[3], original[3 * 80 + 1]);
+
+        chunk.transpose_in_place();
+        assert_eq!(chunk.layout(), Layout::RowMajor);
+        assert_eq!(chunk.as_slice(), original);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "chunk is column-major")]
+    fn row_accessors_reject_column_major_chunks() {
+        let mut chunk = AnySizeMemoryChunk::new(NumVectors::from(4), NumDimensions::from(2));
+        chunk.transpose_in_place();
+        chunk.get_vec(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk is column-major")]
+    fn resizing_rejects_column_major_chunks() {
+        let mut chunk = AnySizeMemoryChunk::new(NumVectors::from(4), NumDimensions::from(2));
+        chunk.transpose_in_place();
+        chunk.use_num_vecs(NumVectors::from(2));
+    }
+
+    #[test]
+    fn serialization_rejects_column_major_chunks() {
+        let mut chunk = AnySizeMemoryChunk::new(NumVectors::from(4), NumDimensions::from(2));
+        chunk.transpose_in_place();
+
+        let error = chunk.to_writer(Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    #[should_panic(expected = "replicated chunk size overflows")]
+    fn replicate_rejects_overflowing_sizes() {
+        let mut chunk = AnySizeMemoryChunk::new(NumVectors::from(2), NumDimensions::from(16));
+        chunk.replicate(usize::MAX / 2);
+    }
+
+    #[test]
+    fn replicate_works() {
+        let mut chunk = AnySizeMemoryChunk::new(NumVectors::from(3), NumDimensions::from(16));
+        chunk.get_vec_mut(1).copy_from_slice(&[1.0; 16]);
+        chunk.use_num_vecs(NumVectors::from(2));
+
+        chunk.replicate(3);
+        assert_eq!(*chunk.num_vecs(), 6);
+        for v in 0..6 {
+            assert_eq!(chunk.get_vec(v), [(v % 2) as f32; 16]);
+        }
+    }
+
+    #[test]
+    fn replicate_keeps_empty_chunks() {
+        let header: Vec<u8> = [1, AnySizeMemoryChunk::BYTE_ORDER_MARK, 0, 16]
+            .iter()
+            .flat_map(|field| field.to_le_bytes())
+            .collect();
+
+        let mut chunk =
+            AnySizeMemoryChunk::from_reader(header.as_slice(), AccessHint::Random).unwrap();
+        chunk.replicate(3);
+        assert_eq!(*chunk.num_vecs(), 0);
+        assert!(chunk.as_slice().is_empty());
+    }
+
+    #[test]
+    fn prefetch_range_works() {
+        let chunk = AnySizeMemoryChunk::new(NumVectors::from(1024), NumDimensions::from(16));
+        chunk.prefetch_range(128, 256);
+        chunk.prefetch();
+    }
+
+    #[test]
+    fn arbitrary_dimensions_work() {
+        let mut chunk = AnySizeMemoryChunk::new(NumVectors::from(3), NumDimensions::from(300));
+        chunk.get_vec_mut(2).copy_from_slice(&[1.0; 300]);
+        assert_eq!(chunk.len(), 900);
+        assert_eq!(chunk.get_vec(1), [0.0; 300]);
+        assert_eq!(chunk.get_vec(2), [1.0; 300]);
+    }
+
+    #[test]
+    fn try_new_reports_size_overflow() {
+        let result =
+            AnySizeMemoryChunk::try_new(NumVectors::from(usize::MAX / 2), NumDimensions::from(16));
+        assert_eq!(result.unwrap_err(), ChunkError::SizeOverflow);
+    }
+
+    #[test]
+    fn get_vec_mut_works() {
+        let mut chunk = AnySizeMemoryChunk::new(NumVectors::from(2), NumDimensions::from(16));
+        chunk.get_vec_mut(1).copy_from_slice(&[1.0; 16]);
+        assert_eq!(chunk.get_vec(0), [0.0; 16]);
+        assert_eq!(chunk.get_vec(1), [1.0; 16]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn get_vec_rejects_out_of_range_index() {
+        let chunk = AnySizeMemoryChunk::new(NumVectors::from(1024), NumDimensions::from(384));
+        chunk.get_vec(1024);
+    }
+
+    #[test]
+    fn use_num_vecs_is_capped_at_allocation() {
+        let mut chunk = AnySizeMemoryChunk::new(NumVectors::from(4), NumDimensions::from(16));
+        chunk.use_num_vecs(NumVectors::from(5));
+        assert_eq!(*chunk.num_vecs(), 4);
+    }
+
+    #[test]
+    fn shrink_to_fit_keeps_used_vectors() {
+        let mut chunk = AnySizeMemoryChunk::new(NumVectors::from(4), NumDimensions::from(16));
+        chunk.as_mut_slice()[16] = 1.0;
+        chunk.use_num_vecs(NumVectors::from(2));
+
+        chunk.shrink_to_fit();
+        chunk.use_num_vecs(NumVectors::from(0));
+        assert_eq!(*chunk.num_vecs(), 2);
+        assert_eq!(chunk.get_vec(1)[0], 1.0);
+    }
+
+    #[test]
+    fn reserve_grows_allocation() {
+        let mut chunk = AnySizeMemoryChunk::new(NumVectors::from(2), NumDimensions::from(16));
+        chunk.as_mut_slice()[16] = 1.0;
+
+        chunk.reserve(NumVectors::from(3));
+        assert_eq!(*chunk.num_vecs(), 2);
+
+        chunk.use_num_vecs(NumVectors::from(0));
+        assert_eq!(*chunk.num_vecs(), 5);
+        assert_eq!(chunk.get_vec(1)[0], 1.0);
+        assert_eq!(chunk.get_vec(4), [0.0; 16]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn lock_memory_works() {
+        let chunk = AnySizeMemoryChunk::new(NumVectors::from(4), NumDimensions::from(16));
+        // Locking may be denied by RLIMIT_MEMLOCK; only a success needs to be reflected.
+        if chunk.lock_memory().is_ok() {
+            assert!(chunk.is_memory_locked());
+        }
     }
 }
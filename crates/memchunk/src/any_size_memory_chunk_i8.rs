@@ -0,0 +1,185 @@
+use crate::any_size_memory_chunk::checked_num_bytes;
+use crate::ChunkError;
+use abstractions::{NumDimensions, NumVectors};
+use alloc_madvise::Memory;
+
+/// A memory chunk storing symmetric int8 scalar-quantized vectors
+/// with one scale factor per vector, using a quarter of the memory of `f32` storage.
+///
+/// A vector `v` is represented as `q * scale`, where `q` is in `-127..=127`.
+#[derive(Debug)]
+pub struct AnySizeMemoryChunkI8 {
+    num_vecs: usize,
+    num_dims: usize,
+    data: Memory,
+    scales: Vec<f32>,
+}
+
+impl AnySizeMemoryChunkI8 {
+    /// Allocates a zeroed chunk for the specified number of vectors.
+    ///
+    /// ## Panics
+    /// Panics if the memory could not be allocated; see [`AnySizeMemoryChunkI8::try_new`]
+    /// for a fallible alternative.
+    pub fn new(num_vectors: NumVectors, num_dimensions: NumDimensions) -> Self {
+        Self::try_new(num_vectors, num_dimensions).expect("memory allocation failed")
+    }
+
+    /// Allocates a zeroed chunk for the specified number of vectors,
+    /// returning an error if the memory could not be allocated.
+    pub fn try_new(
+        num_vectors: NumVectors,
+        num_dimensions: NumDimensions,
+    ) -> Result<Self, ChunkError> {
+        let num_bytes = checked_num_bytes::<i8>(num_vectors, num_dimensions)?;
+        let chunk = Memory::allocate(num_bytes, false, true)?;
+
+        Ok(Self {
+            data: chunk,
+            scales: vec![0.0; *num_vectors],
+            num_vecs: *num_vectors,
+            num_dims: *num_dimensions,
+        })
+    }
+
+    pub fn num_vecs(&self) -> NumVectors {
+        NumVectors::from(self.num_vecs)
+    }
+
+    pub fn num_dims(&self) -> NumDimensions {
+        NumDimensions::from(self.num_dims)
+    }
+
+    /// Quantizes the vector and stores it at the specified index.
+    pub fn set_vec(&mut self, idx: usize, vec: &[f32]) {
+        assert!(idx < self.num_vecs, "vector index out of bounds");
+        assert_eq!(vec.len(), self.num_dims, "vector dimension mismatch");
+
+        let start = idx * self.num_dims;
+        let end = start + self.num_dims;
+        let data: &mut [i8] = self.data.as_mut();
+        self.scales[idx] = quantize(vec, &mut data[start..end]);
+    }
+
+    /// Returns the quantized values and the scale factor of the vector at the specified index.
+    pub fn get_quantized_vec(&self, idx: usize) -> (&[i8], f32) {
+        assert!(idx < self.num_vecs, "vector index out of bounds");
+        let start = idx * self.num_dims;
+        let end = start + self.num_dims;
+        let data: &[i8] = self.data.as_ref();
+        (&data[start..end], self.scales[idx])
+    }
+
+    /// Returns the dequantized vector at the specified index.
+    pub fn get_vec(&self, idx: usize) -> Vec<f32> {
+        let (values, scale) = self.get_quantized_vec(idx);
+        values.iter().map(|&q| q as f32 * scale).collect()
+    }
+
+    /// Calculates the dot products of the query against all stored vectors.
+    ///
+    /// The query is quantized itself, products are accumulated in `i64`
+    /// and rescaled once per vector.
+    pub fn dot_product(&self, query: &[f32], results: &mut [f32]) {
+        assert_eq!(
+            query.len(),
+            self.num_dims,
+            "query vector dimension mismatch"
+        );
+        assert_eq!(
+            results.len(),
+            self.num_vecs,
+            "result vector dimension mismatch"
+        );
+
+        let mut quantized_query = vec![0; self.num_dims];
+        let query_scale = quantize(query, &mut quantized_query);
+
+        let data: &[i8] = self.data.as_ref();
+        for (v, result) in results.iter_mut().enumerate() {
+            let start = v * self.num_dims;
+            let row = &data[start..start + self.num_dims];
+            // An i32 sum would overflow beyond about 133k dimensions of ±127 products.
+            let sum: i64 = quantized_query
+                .iter()
+                .zip(row)
+                .map(|(&q, &r)| (q as i32 * r as i32) as i64)
+                .sum();
+            *result = sum as f32 * query_scale * self.scales[v];
+        }
+    }
+}
+
+/// Quantizes the values into `dest` and returns the scale factor.
+fn quantize(values: &[f32], dest: &mut [i8]) -> f32 {
+    debug_assert_eq!(values.len(), dest.len());
+
+    let max_abs = values.iter().fold(0.0f32, |max, v| max.max(v.abs()));
+    if max_abs == 0.0 {
+        dest.fill(0);
+        return 0.0;
+    }
+
+    let scale = max_abs / i8::MAX as f32;
+    let inv_scale = 1.0 / scale;
+    for (q, &v) in dest.iter_mut().zip(values) {
+        *q = (v * inv_scale).round().clamp(-127.0, 127.0) as i8;
+    }
+    scale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{generate_test_vectors, rmse};
+    use crate::{DotProduct, ReferenceDotProduct};
+
+    #[test]
+    fn roundtrip_works() {
+        let mut chunk = AnySizeMemoryChunkI8::new(NumVectors::from(2), NumDimensions::from(16));
+        let vec: Vec<f32> = (0..16).map(|i| i as f32 - 8.0).collect();
+        chunk.set_vec(1, &vec);
+
+        assert_eq!(chunk.get_vec(0), vec![0.0; 16]);
+        assert!(rmse(&chunk.get_vec(1), &vec) < 0.05);
+    }
+
+    #[test]
+    fn quantized_dot_product_supports_many_dimensions() {
+        let num_dims = 140_000;
+        let mut chunk =
+            AnySizeMemoryChunkI8::new(NumVectors::from(1), NumDimensions::from(num_dims));
+        chunk.set_vec(0, &vec![1.0; num_dims]);
+
+        let mut results = [0.0];
+        chunk.dot_product(&vec![1.0; num_dims], &mut results);
+        assert!((results[0] - num_dims as f32).abs() < 1.0);
+    }
+
+    #[test]
+    fn quantized_dot_product_works() {
+        let num_dims = 384;
+        let num_vecs = 128;
+        let (query, data) = generate_test_vectors(num_dims, num_vecs);
+
+        let mut chunk =
+            AnySizeMemoryChunkI8::new(NumVectors::from(num_vecs), NumDimensions::from(num_dims));
+        for (v, vec) in data.chunks_exact(num_dims).enumerate() {
+            chunk.set_vec(v, vec);
+        }
+
+        let mut reference = vec![0.0; num_vecs];
+        ReferenceDotProduct::default().dot_product(
+            &query,
+            &data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut reference,
+        );
+
+        let mut results = vec![0.0; num_vecs];
+        chunk.dot_product(&query, &mut results);
+
+        assert!(rmse(&reference, &results) < 0.1);
+    }
+}
@@ -0,0 +1,130 @@
+use crate::AnySizeMemoryChunk;
+use abstractions::{NumDimensions, NumVectors};
+use rayon::prelude::*;
+
+/// Accumulates the mean (centroid) of a set of vectors.
+///
+/// The mean is updated incrementally using Welford's method in double precision,
+/// so it neither overflows nor loses precision the way a large running `f32` sum would.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Centroid {
+    mean: Vec<f64>,
+    count: usize,
+}
+
+impl Centroid {
+    /// Creates an empty accumulator for vectors of the specified dimension.
+    pub fn new(num_dims: NumDimensions) -> Self {
+        Self {
+            mean: vec![0.0; *num_dims],
+            count: 0,
+        }
+    }
+
+    /// Calculates the centroid of all vectors of the chunk in parallel.
+    pub fn par_mean(chunk: &AnySizeMemoryChunk) -> Vec<f32> {
+        let num_dims = chunk.num_dims();
+        chunk
+            .par_rows()
+            .fold(
+                || Centroid::new(num_dims),
+                |mut centroid, row| {
+                    centroid.add(row);
+                    centroid
+                },
+            )
+            .reduce(
+                || Centroid::new(num_dims),
+                |mut lhs, rhs| {
+                    lhs.merge(&rhs);
+                    lhs
+                },
+            )
+            .mean()
+    }
+
+    pub fn num_dims(&self) -> NumDimensions {
+        self.mean.len().into()
+    }
+
+    /// The number of vectors added so far.
+    pub fn count(&self) -> NumVectors {
+        self.count.into()
+    }
+
+    /// Adds a vector to the centroid.
+    ///
+    /// ## Panics
+    /// Panics if the vector does not match the accumulator's dimension.
+    pub fn add(&mut self, vec: &[f32]) {
+        assert_eq!(vec.len(), self.mean.len(), "vector dimension mismatch");
+        self.count += 1;
+        let n = self.count as f64;
+        for (mean, &x) in self.mean.iter_mut().zip(vec) {
+            *mean += (x as f64 - *mean) / n;
+        }
+    }
+
+    /// Adds all vectors of the chunk to the centroid.
+    pub fn add_chunk(&mut self, chunk: &AnySizeMemoryChunk) {
+        for row in chunk.rows() {
+            self.add(row);
+        }
+    }
+
+    /// Combines the vectors accumulated by another centroid into this one.
+    pub fn merge(&mut self, other: &Centroid) {
+        assert_eq!(
+            other.mean.len(),
+            self.mean.len(),
+            "vector dimension mismatch"
+        );
+        let count = self.count + other.count;
+        if count == 0 {
+            return;
+        }
+
+        let weight = other.count as f64 / count as f64;
+        for (mean, &other) in self.mean.iter_mut().zip(&other.mean) {
+            *mean += (other - *mean) * weight;
+        }
+        self.count = count;
+    }
+
+    /// The mean of all vectors added so far; all zeros if no vector was added.
+    pub fn mean(&self) -> Vec<f32> {
+        self.mean.iter().map(|&x| x as f32).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn centroid_works() {
+        let mut chunk = AnySizeMemoryChunk::new(4.into(), 2.into());
+        chunk
+            .as_mut_slice()
+            .copy_from_slice(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
+
+        let mut centroid = Centroid::new(2.into());
+        assert_eq!(centroid.mean(), [0.0, 0.0]);
+
+        centroid.add(&[1.0, 2.0]);
+        assert_eq!(centroid.mean(), [1.0, 2.0]);
+
+        centroid.add_chunk(&chunk);
+        assert_eq!(*centroid.count(), 5);
+        assert_eq!(centroid.mean(), [3.4, 4.4]);
+
+        assert_eq!(Centroid::par_mean(&chunk), [4.0, 5.0]);
+
+        let mut merged = Centroid::new(2.into());
+        merged.add_chunk(&chunk);
+        let mut single = Centroid::new(2.into());
+        single.add(&[1.0, 2.0]);
+        merged.merge(&single);
+        assert_eq!(merged.mean(), centroid.mean());
+    }
+}
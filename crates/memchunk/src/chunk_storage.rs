@@ -0,0 +1,142 @@
+use alloc_madvise::Memory;
+use std::ffi::c_void;
+
+/// The memory backing an [`AnySizeMemoryChunk`](crate::AnySizeMemoryChunk).
+pub(crate) enum ChunkStorage {
+    /// Memory allocated for the chunk.
+    Allocated(Memory),
+    /// A private mapping of a chunk file; see [`MappedFile`].
+    #[cfg(unix)]
+    Mapped(MappedFile),
+}
+
+impl ChunkStorage {
+    /// Returns a pointer to the start of the backing memory, e.g. for `mlock`.
+    pub fn as_ptr(&self) -> *const c_void {
+        match self {
+            ChunkStorage::Allocated(memory) => memory.as_ptr(),
+            #[cfg(unix)]
+            ChunkStorage::Mapped(file) => file.address,
+        }
+    }
+
+    /// The number of bytes of the backing memory.
+    pub fn len(&self) -> usize {
+        match self {
+            ChunkStorage::Allocated(memory) => memory.len(),
+            #[cfg(unix)]
+            ChunkStorage::Mapped(file) => file.num_bytes,
+        }
+    }
+}
+
+impl From<Memory> for ChunkStorage {
+    fn from(memory: Memory) -> Self {
+        ChunkStorage::Allocated(memory)
+    }
+}
+
+/// Empty allocations have a null address, so they are mapped to an empty slice.
+impl AsRef<[f32]> for ChunkStorage {
+    fn as_ref(&self) -> &[f32] {
+        match self {
+            ChunkStorage::Allocated(memory) if memory.is_empty() => &[],
+            ChunkStorage::Allocated(memory) => memory.as_ref(),
+            #[cfg(unix)]
+            ChunkStorage::Mapped(file) => file.payload(),
+        }
+    }
+}
+
+impl AsMut<[f32]> for ChunkStorage {
+    fn as_mut(&mut self) -> &mut [f32] {
+        match self {
+            ChunkStorage::Allocated(memory) if memory.is_empty() => &mut [],
+            ChunkStorage::Allocated(memory) => memory.as_mut(),
+            #[cfg(unix)]
+            ChunkStorage::Mapped(file) => file.payload_mut(),
+        }
+    }
+}
+
+/// A private, copy-on-write memory mapping of a file holding an `f32` payload.
+///
+/// Pages are read from the file when first accessed; modifications are never
+/// written back to the file.
+#[cfg(unix)]
+pub(crate) struct MappedFile {
+    address: *mut c_void,
+    num_bytes: usize,
+    payload_offset: usize,
+    num_elems: usize,
+}
+
+#[cfg(unix)]
+impl MappedFile {
+    /// Maps the first `payload_offset + num_elems * 4` bytes of the file.
+    ///
+    /// ## Safety
+    /// The file must be at least that long, and `payload_offset` must be a multiple of four.
+    pub unsafe fn map(
+        file: &std::fs::File,
+        payload_offset: usize,
+        num_elems: usize,
+        sequential: bool,
+    ) -> Result<Self, std::io::Error> {
+        use std::os::unix::io::AsRawFd;
+
+        debug_assert_eq!(payload_offset % std::mem::size_of::<f32>(), 0);
+        let num_bytes = payload_offset + num_elems * std::mem::size_of::<f32>();
+        let address = libc::mmap(
+            std::ptr::null_mut(),
+            num_bytes,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE,
+            file.as_raw_fd(),
+            0,
+        );
+        if address == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        // The advice is only a hint, so failures are ignored.
+        let advice = if sequential {
+            libc::MADV_SEQUENTIAL
+        } else {
+            libc::MADV_RANDOM
+        };
+        libc::madvise(address, num_bytes, advice);
+
+        Ok(Self {
+            address,
+            num_bytes,
+            payload_offset,
+            num_elems,
+        })
+    }
+
+    fn payload(&self) -> &[f32] {
+        // SAFETY: The mapping covers the payload, which is aligned since the mapping
+        // is page-aligned and the offset is a multiple of four.
+        unsafe {
+            let data = (self.address as *const u8).add(self.payload_offset);
+            std::slice::from_raw_parts(data as *const f32, self.num_elems)
+        }
+    }
+
+    fn payload_mut(&mut self) -> &mut [f32] {
+        // SAFETY: See payload(); the mapping is private and writable.
+        unsafe {
+            let data = (self.address as *mut u8).add(self.payload_offset);
+            std::slice::from_raw_parts_mut(data as *mut f32, self.num_elems)
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for MappedFile {
+    fn drop(&mut self) {
+        // SAFETY: The mapping was created in map() and is not referenced anymore.
+        unsafe { libc::munmap(self.address, self.num_bytes) };
+    }
+}
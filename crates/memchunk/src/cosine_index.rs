@@ -0,0 +1,87 @@
+use crate::dot_product::{DotProduct, ReferenceDotProductParallel};
+use crate::AnySizeMemoryChunk;
+use rayon::prelude::*;
+
+/// Answers repeated cosine similarity queries against the vectors of a chunk.
+///
+/// The L2 norms of the data vectors are calculated once on construction, so each query
+/// only requires a plain dot product, the norm of the query and one division per vector.
+/// As with [`Metric::Cosine`](crate::Metric::Cosine), vectors without magnitude score zero.
+pub struct CosineIndex<'a> {
+    chunk: &'a AnySizeMemoryChunk,
+    norms: Vec<f32>,
+}
+
+impl<'a> CosineIndex<'a> {
+    /// Creates the index, calculating the norms of all vectors of the chunk in parallel.
+    pub fn new(chunk: &'a AnySizeMemoryChunk) -> Self {
+        let norms = chunk
+            .par_rows()
+            .map(|row| row.iter().fold(0.0f32, |sum, &x| sum + x * x).sqrt())
+            .collect();
+        Self { chunk, norms }
+    }
+
+    /// The cached L2 norms of the data vectors.
+    pub fn norms(&self) -> &[f32] {
+        &self.norms
+    }
+
+    /// Calculates the cosine similarities of the query against all data vectors.
+    pub fn query(&self, query: &[f32], results: &mut [f32]) {
+        ReferenceDotProductParallel::default().dot_product(
+            query,
+            self.chunk.as_slice(),
+            self.chunk.num_dims(),
+            self.chunk.num_vecs(),
+            results,
+        );
+
+        let query_norm = query.iter().fold(0.0f32, |sum, &x| sum + x * x).sqrt();
+        results
+            .par_iter_mut()
+            .zip(&self.norms)
+            .for_each(|(result, &norm)| {
+                let norm = query_norm * norm;
+                *result = if norm == 0.0 { 0.0 } else { *result / norm };
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{generate_test_vectors, rmse};
+    use crate::{compute_metric, Metric};
+
+    #[test]
+    fn cosine_index_matches_metric() {
+        let num_dims = 100;
+        let num_vecs = 300;
+        let (query, data) = generate_test_vectors(num_dims, num_vecs);
+
+        let mut chunk = AnySizeMemoryChunk::new(num_vecs.into(), num_dims.into());
+        chunk.as_mut_slice().copy_from_slice(&data);
+        chunk.as_mut_slice()[..num_dims].fill(0.0);
+
+        let mut expected = vec![0.0; num_vecs];
+        compute_metric(
+            Metric::Cosine,
+            &query,
+            &data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut expected,
+            false,
+        );
+        expected[0] = 0.0;
+
+        let index = CosineIndex::new(&chunk);
+        assert_eq!(index.norms().len(), num_vecs);
+
+        let mut results = vec![f32::NAN; num_vecs];
+        index.query(&query, &mut results);
+        assert_eq!(results[0], 0.0);
+        assert!(rmse(&expected, &results) < 1e-6);
+    }
+}
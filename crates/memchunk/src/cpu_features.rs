@@ -0,0 +1,47 @@
+/// Returns the SIMD-relevant CPU features that are available at runtime.
+///
+/// On x86-64 this checks for `sse`, `avx`, `avx2`, `fma` and `avx512f`;
+/// on AArch64 it reports `neon`. Features that are not detected are omitted.
+pub fn detected_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("sse") {
+            features.push("sse");
+        }
+        if is_x86_feature_detected!("avx") {
+            features.push("avx");
+        }
+        if is_x86_feature_detected!("avx2") {
+            features.push("avx2");
+        }
+        if is_x86_feature_detected!("fma") {
+            features.push("fma");
+        }
+        if is_x86_feature_detected!("avx512f") {
+            features.push("avx512f");
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        if cfg!(target_feature = "neon") {
+            features.push("neon");
+        }
+    }
+
+    features
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn sse_is_detected_on_x86_64() {
+        // SSE is part of the x86-64 baseline.
+        assert!(detected_features().contains(&"sse"));
+    }
+}
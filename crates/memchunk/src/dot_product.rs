@@ -1,5 +1,12 @@
+use crate::topk::{topk, Entry};
 use abstractions::{NumDimensions, NumVectors};
 use rayon::prelude::*;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// The number of data rows processed between two progress reports
+/// in [`DotProduct::dot_product_with_progress`].
+pub const PROGRESS_BLOCK_ROWS: usize = 65536;
 
 pub trait DotProduct {
     fn dot_product(
@@ -10,8 +17,171 @@ pub trait DotProduct {
         num_vecs: NumVectors,
         results: &mut [f32],
     );
+
+    /// Calculates the dot products of multiple queries against all data vectors.
+    ///
+    /// The default implementation calls [`DotProduct::dot_product`] once per query;
+    /// implementations may override it with a cache-friendlier approach.
+    ///
+    /// ## Arguments
+    /// * `queries` - The row-major `num_queries × num_dims` query matrix.
+    /// * `num_queries` - The number of query vectors.
+    /// * `data` - The row-major `num_vecs × num_dims` data matrix.
+    /// * `results` - The row-major `num_queries × num_vecs` result matrix, i.e.
+    ///   the score of query `q` against vector `v` is stored at `q * num_vecs + v`.
+    fn dot_product_batch(
+        &self,
+        queries: &[f32],
+        num_queries: NumVectors,
+        data: &[f32],
+        num_dims: NumDimensions,
+        num_vecs: NumVectors,
+        results: &mut [f32],
+    ) {
+        let num_queries = num_queries.into_inner();
+        let dims = num_dims.into_inner();
+        let vecs = num_vecs.into_inner();
+
+        debug_assert_eq!(
+            queries.len(),
+            num_queries * dims,
+            "query matrix dimension mismatch"
+        );
+        debug_assert_eq!(
+            results.len(),
+            num_queries * vecs,
+            "result matrix dimension mismatch"
+        );
+
+        for q in 0..num_queries {
+            let query = &queries[q * dims..(q + 1) * dims];
+            let results = &mut results[q * vecs..(q + 1) * vecs];
+            self.dot_product(query, data, num_dims, num_vecs, results);
+        }
+    }
+
+    /// Calculates the dot products of the query against a subset of the data vectors.
+    ///
+    /// The default implementation calls [`DotProduct::dot_product`] once per index.
+    ///
+    /// ## Arguments
+    /// * `data` - The row-major data matrix.
+    /// * `indices` - The indices of the data vectors to score.
+    /// * `results` - The scores, where `results[i]` holds the score of vector `indices[i]`.
+    fn dot_product_indexed(
+        &self,
+        query: &[f32],
+        data: &[f32],
+        num_dims: NumDimensions,
+        indices: &[usize],
+        results: &mut [f32],
+    ) {
+        let dims = num_dims.into_inner();
+
+        debug_assert_eq!(query.len(), dims, "query vector dimension mismatch");
+        debug_assert_eq!(
+            results.len(),
+            indices.len(),
+            "result vector dimension mismatch"
+        );
+
+        for (&v, result) in indices.iter().zip(results.iter_mut()) {
+            let row = &data[v * dims..(v + 1) * dims];
+            self.dot_product(
+                query,
+                row,
+                num_dims,
+                NumVectors::from(1),
+                std::slice::from_mut(result),
+            );
+        }
+    }
+
+    /// Calculates the dot products of the query against all data vectors,
+    /// reporting `(rows_done, total)` to the `progress` callback after every block of
+    /// [`PROGRESS_BLOCK_ROWS`] vectors.
+    ///
+    /// Vectors without dimensions have a dot product of zero; progress is then reported once.
+    fn dot_product_with_progress<P: FnMut(usize, usize)>(
+        &self,
+        query: &[f32],
+        data: &[f32],
+        num_dims: NumDimensions,
+        num_vecs: NumVectors,
+        results: &mut [f32],
+        mut progress: P,
+    ) {
+        let dims = num_dims.into_inner();
+        let total = num_vecs.into_inner();
+
+        debug_assert_eq!(results.len(), total, "result vector dimension mismatch");
+        debug_assert_eq!(data.len(), total * dims, "data buffer dimension mismatch");
+
+        if dims == 0 {
+            results.fill(0.0);
+            progress(total, total);
+            return;
+        }
+
+        let mut rows_done = 0;
+        for (block, results) in data
+            .chunks(PROGRESS_BLOCK_ROWS * dims)
+            .zip(results.chunks_mut(PROGRESS_BLOCK_ROWS))
+        {
+            let block_vecs = results.len();
+            self.dot_product(query, block, num_dims, block_vecs.into(), results);
+            rows_done += block_vecs;
+            progress(rows_done, total);
+        }
+    }
+
+    /// Calculates the dot products of the query against all data vectors
+    /// and returns the `K` entries with the largest scores.
+    ///
+    /// The resulting entries are not guaranteed to be sorted.
+    fn dot_product_topk<const K: usize>(
+        &self,
+        query: &[f32],
+        data: &[f32],
+        num_dims: NumDimensions,
+        num_vecs: NumVectors,
+    ) -> [Entry; K] {
+        let mut scores = vec![0.0; num_vecs.into_inner()];
+        self.dot_product(query, data, num_dims, num_vecs, &mut scores);
+        topk::<K, _>(&mut scores)
+    }
 }
 
+/// An error indicating that the buffers passed to a dot product do not match its dimensions.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DotProductError {
+    /// The query vector does not have the specified number of dimensions.
+    QueryLength { expected: usize, actual: usize },
+    /// The data buffer does not hold the specified number of vectors.
+    DataLength { expected: usize, actual: usize },
+    /// The result buffer does not hold one element per vector.
+    ResultLength { expected: usize, actual: usize },
+}
+
+impl Display for DotProductError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DotProductError::QueryLength { expected, actual } => {
+                write!(f, "query vector has {actual} elements, expected {expected}")
+            }
+            DotProductError::DataLength { expected, actual } => {
+                write!(f, "data buffer has {actual} elements, expected {expected}")
+            }
+            DotProductError::ResultLength { expected, actual } => write!(
+                f,
+                "result buffer has {actual} elements, expected {expected}"
+            ),
+        }
+    }
+}
+
+impl Error for DotProductError {}
+
 #[derive(Default)]
 pub struct ReferenceDotProduct {}
 
@@ -53,6 +223,45 @@ impl DotProduct for ReferenceDotProduct {
             *result = sum;
         }
     }
+
+    /// Calculates the dot products of multiple queries against all data vectors,
+    /// loading each tile of data vectors once for all queries.
+    fn dot_product_batch(
+        &self,
+        queries: &[f32],
+        num_queries: NumVectors,
+        data: &[f32],
+        num_dims: NumDimensions,
+        num_vecs: NumVectors,
+        results: &mut [f32],
+    ) {
+        let num_queries = num_queries.into_inner();
+        let num_vecs = num_vecs.into_inner();
+        let num_dims = num_dims.into_inner();
+
+        debug_assert_eq!(
+            queries.len(),
+            num_queries * num_dims,
+            "query matrix dimension mismatch"
+        );
+        debug_assert_eq!(
+            results.len(),
+            num_queries * num_vecs,
+            "result matrix dimension mismatch"
+        );
+        debug_assert_eq!(
+            data.len(),
+            num_vecs * num_dims,
+            "data buffer dimension mismatch"
+        );
+
+        for (t, tile) in data.chunks(BATCH_TILE_ROWS * num_dims).enumerate() {
+            let first_vec = t * BATCH_TILE_ROWS;
+            dot_product_tile(queries, tile, num_dims, |q, v, sum| {
+                results[q * num_vecs + first_vec + v] = sum;
+            });
+        }
+    }
 }
 
 impl DotProduct for ReferenceDotProductParallel {
@@ -90,6 +299,89 @@ impl DotProduct for ReferenceDotProductParallel {
                 *result = sum;
             });
     }
+
+    /// Calculates the dot products of the query against a subset of the data vectors,
+    /// processing the indices in parallel.
+    fn dot_product_indexed(
+        &self,
+        query: &[f32],
+        data: &[f32],
+        num_dims: NumDimensions,
+        indices: &[usize],
+        results: &mut [f32],
+    ) {
+        let num_dims = num_dims.into_inner();
+
+        debug_assert_eq!(query.len(), num_dims, "query vector dimension mismatch");
+        debug_assert_eq!(
+            results.len(),
+            indices.len(),
+            "result vector dimension mismatch"
+        );
+
+        results
+            .par_iter_mut()
+            .zip(indices.par_iter())
+            .for_each(|(result, &v)| {
+                let row = &data[v * num_dims..(v + 1) * num_dims];
+                *result = query.iter().zip(row).fold(0.0, |sum, (&q, &r)| sum + r * q);
+            });
+    }
+
+    /// Calculates the dot products of multiple queries against all data vectors,
+    /// processing tiles of data vectors in parallel.
+    fn dot_product_batch(
+        &self,
+        queries: &[f32],
+        num_queries: NumVectors,
+        data: &[f32],
+        num_dims: NumDimensions,
+        num_vecs: NumVectors,
+        results: &mut [f32],
+    ) {
+        let num_queries = num_queries.into_inner();
+        let num_vecs = num_vecs.into_inner();
+        let num_dims = num_dims.into_inner();
+
+        debug_assert_eq!(
+            queries.len(),
+            num_queries * num_dims,
+            "query matrix dimension mismatch"
+        );
+        debug_assert_eq!(
+            results.len(),
+            num_queries * num_vecs,
+            "result matrix dimension mismatch"
+        );
+        debug_assert_eq!(
+            data.len(),
+            num_vecs * num_dims,
+            "data buffer dimension mismatch"
+        );
+
+        // Each tile produces a query-major block of scores that is scattered
+        // into the result matrix afterwards.
+        let tiles: Vec<Vec<f32>> = data
+            .par_chunks(BATCH_TILE_ROWS * num_dims)
+            .map(|tile| {
+                let tile_vecs = tile.len() / num_dims;
+                let mut scores = vec![0.0; num_queries * tile_vecs];
+                dot_product_tile(queries, tile, num_dims, |q, v, sum| {
+                    scores[q * tile_vecs + v] = sum;
+                });
+                scores
+            })
+            .collect();
+
+        for (t, scores) in tiles.iter().enumerate() {
+            let first_vec = t * BATCH_TILE_ROWS;
+            let tile_vecs = BATCH_TILE_ROWS.min(num_vecs - first_vec);
+            for (q, row) in scores.chunks_exact(tile_vecs).enumerate() {
+                let start = q * num_vecs + first_vec;
+                results[start..start + tile_vecs].copy_from_slice(row);
+            }
+        }
+    }
 }
 
 impl<const UNROLL_FACTOR: usize> DotProduct for ReferenceDotProductUnrolled<UNROLL_FACTOR> {
@@ -112,21 +404,44 @@ impl<const UNROLL_FACTOR: usize> DotProduct for ReferenceDotProductUnrolled<UNRO
             "data buffer dimension mismatch"
         );
 
+        let unrolled_dims = num_dims - num_dims % UNROLL_FACTOR;
+
         let data: &[f32] = data.as_ref();
         for (v, result) in results.iter_mut().enumerate() {
             let start_index = v * num_dims;
 
             let mut sum = [0.0; UNROLL_FACTOR];
-            for d in (0..num_dims).step_by(UNROLL_FACTOR) {
+            for d in (0..unrolled_dims).step_by(UNROLL_FACTOR) {
                 Self::unrolled_dots(query, data, d, start_index + d, &mut sum);
             }
 
-            *result = sum.iter().sum();
+            // Handle the remaining dimensions that do not fill an entire unrolled block.
+            let mut tail = 0.0;
+            for d in unrolled_dims..num_dims {
+                tail += data[start_index + d] * query[d];
+            }
+
+            *result = sum.iter().sum::<f32>() + tail;
         }
     }
 }
 
 impl<const UNROLL_FACTOR: usize> ReferenceDotProductUnrolled<UNROLL_FACTOR> {
+    /// Calculates the dot products like [`DotProduct::dot_product`], but validates
+    /// that all buffers can accommodate the unrolled accesses before entering the hot loop.
+    pub fn try_dot_product(
+        &self,
+        query: &[f32],
+        data: &[f32],
+        num_dims: NumDimensions,
+        num_vecs: NumVectors,
+        results: &mut [f32],
+    ) -> Result<(), DotProductError> {
+        validate_buffers(query, data, num_dims, num_vecs, results)?;
+        self.dot_product(query, data, num_dims, num_vecs, results);
+        Ok(())
+    }
+
     #[inline(always)]
     #[unroll::unroll_for_loops]
     fn unrolled_dots(
@@ -136,6 +451,15 @@ impl<const UNROLL_FACTOR: usize> ReferenceDotProductUnrolled<UNROLL_FACTOR> {
         data_start_index: usize,
         sum: &mut [f32; UNROLL_FACTOR],
     ) {
+        debug_assert!(
+            query_start_index + UNROLL_FACTOR <= query.len(),
+            "unrolled access exceeds the query vector"
+        );
+        debug_assert!(
+            data_start_index + UNROLL_FACTOR <= data.len(),
+            "unrolled access exceeds the data buffer"
+        );
+
         for unroll in 0..UNROLL_FACTOR {
             let r = data[data_start_index + unroll];
             let q = query[query_start_index + unroll];
@@ -144,9 +468,65 @@ impl<const UNROLL_FACTOR: usize> ReferenceDotProductUnrolled<UNROLL_FACTOR> {
     }
 }
 
+/// Validates that the buffers passed to a dot product match the specified dimensions.
+fn validate_buffers(
+    query: &[f32],
+    data: &[f32],
+    num_dims: NumDimensions,
+    num_vecs: NumVectors,
+    results: &[f32],
+) -> Result<(), DotProductError> {
+    let num_dims = num_dims.into_inner();
+    let num_vecs = num_vecs.into_inner();
+
+    if query.len() != num_dims {
+        return Err(DotProductError::QueryLength {
+            expected: num_dims,
+            actual: query.len(),
+        });
+    }
+
+    if data.len() != num_vecs * num_dims {
+        return Err(DotProductError::DataLength {
+            expected: num_vecs * num_dims,
+            actual: data.len(),
+        });
+    }
+
+    if results.len() != num_vecs {
+        return Err(DotProductError::ResultLength {
+            expected: num_vecs,
+            actual: results.len(),
+        });
+    }
+
+    Ok(())
+}
+
+/// The number of data rows processed as one tile in the batched dot products.
+const BATCH_TILE_ROWS: usize = 64;
+
+/// Dots every query against every row of a tile of data vectors,
+/// reporting the score of query `q` and tile row `v` to the `store` callback.
+#[inline(always)]
+fn dot_product_tile<F: FnMut(usize, usize, f32)>(
+    queries: &[f32],
+    tile: &[f32],
+    num_dims: usize,
+    mut store: F,
+) {
+    for (q, query) in queries.chunks_exact(num_dims).enumerate() {
+        for (v, row) in tile.chunks_exact(num_dims).enumerate() {
+            let sum = query.iter().zip(row).fold(0.0, |sum, (&q, &r)| sum + r * q);
+            store(q, v, sum);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::{generate_test_vectors, rmse};
 
     #[test]
     fn simple_works() {
@@ -166,4 +546,214 @@ mod tests {
 
         assert_eq!(results, [12., 12., 0., 6.])
     }
+
+    #[test]
+    fn unrolled_handles_remainder() {
+        let num_dims = 10;
+        let num_vecs = 4;
+        let (query, data) = generate_test_vectors(num_dims, num_vecs);
+
+        let mut reference = vec![0.0; num_vecs];
+        ReferenceDotProduct::default().dot_product(
+            &query,
+            &data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut reference,
+        );
+
+        let mut results = vec![0.0; num_vecs];
+        ReferenceDotProductUnrolled::<4>::default().dot_product(
+            &query,
+            &data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut results,
+        );
+
+        assert!(rmse(&reference, &results) < 1e-6);
+    }
+
+    #[test]
+    fn batch_works() {
+        let num_dims = 16;
+        let num_vecs = 150;
+        let num_queries = 3;
+        let (_, data) = generate_test_vectors(num_dims, num_vecs);
+        let queries = &data[..num_queries * num_dims];
+
+        let reference = ReferenceDotProduct::default();
+        let mut expected = vec![0.0; num_queries * num_vecs];
+        for (query, results) in queries
+            .chunks_exact(num_dims)
+            .zip(expected.chunks_exact_mut(num_vecs))
+        {
+            reference.dot_product(query, &data, num_dims.into(), num_vecs.into(), results);
+        }
+
+        let mut results = vec![0.0; num_queries * num_vecs];
+        reference.dot_product_batch(
+            queries,
+            num_queries.into(),
+            &data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut results,
+        );
+        assert!(rmse(&expected, &results) < 1e-6);
+
+        let mut results = vec![0.0; num_queries * num_vecs];
+        ReferenceDotProductUnrolled::<4>::default().dot_product_batch(
+            queries,
+            num_queries.into(),
+            &data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut results,
+        );
+        assert!(rmse(&expected, &results) < 1e-6);
+
+        let mut results = vec![0.0; num_queries * num_vecs];
+        ReferenceDotProductParallel::default().dot_product_batch(
+            queries,
+            num_queries.into(),
+            &data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut results,
+        );
+        assert!(rmse(&expected, &results) < 1e-6);
+    }
+
+    #[test]
+    fn indexed_works() {
+        let num_dims = 16;
+        let num_vecs = 100;
+        let (query, data) = generate_test_vectors(num_dims, num_vecs);
+
+        let mut all = vec![0.0; num_vecs];
+        ReferenceDotProduct::default().dot_product(
+            &query,
+            &data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut all,
+        );
+
+        let indices = [97, 3, 42, 3];
+        let expected: Vec<f32> = indices.iter().map(|&v| all[v]).collect();
+
+        let mut results = vec![0.0; indices.len()];
+        ReferenceDotProduct::default().dot_product_indexed(
+            &query,
+            &data,
+            num_dims.into(),
+            &indices,
+            &mut results,
+        );
+        assert!(rmse(&expected, &results) < 1e-6);
+
+        let mut results = vec![0.0; indices.len()];
+        ReferenceDotProductParallel::default().dot_product_indexed(
+            &query,
+            &data,
+            num_dims.into(),
+            &indices,
+            &mut results,
+        );
+        assert!(rmse(&expected, &results) < 1e-6);
+    }
+
+    #[test]
+    fn progress_is_reported_per_block() {
+        let num_dims = 16;
+        let num_vecs = PROGRESS_BLOCK_ROWS + 100;
+        let (query, data) = generate_test_vectors(num_dims, num_vecs);
+
+        let reference = ReferenceDotProduct::default();
+        let mut expected = vec![0.0; num_vecs];
+        reference.dot_product(
+            &query,
+            &data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut expected,
+        );
+
+        let mut reports = Vec::new();
+        let mut results = vec![0.0; num_vecs];
+        reference.dot_product_with_progress(
+            &query,
+            &data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut results,
+            |done, total| reports.push((done, total)),
+        );
+
+        assert_eq!(
+            reports,
+            [(PROGRESS_BLOCK_ROWS, num_vecs), (num_vecs, num_vecs)]
+        );
+        assert!(rmse(&expected, &results) < 1e-6);
+    }
+
+    #[test]
+    fn progress_handles_zero_dimensions() {
+        let mut reports = Vec::new();
+        let mut results = vec![f32::NAN; 3];
+        ReferenceDotProduct::default().dot_product_with_progress(
+            &[],
+            &[],
+            0.into(),
+            3.into(),
+            &mut results,
+            |done, total| reports.push((done, total)),
+        );
+
+        assert_eq!(reports, [(3, 3)]);
+        assert_eq!(results, [0.0; 3]);
+    }
+
+    #[test]
+    fn try_unrolled_rejects_short_query() {
+        let query = vec![1., 2.];
+        let data = vec![4., -5., 6., 4., -5., 6.];
+        let mut results = vec![0., 0.];
+
+        let result = ReferenceDotProductUnrolled::<3>::default().try_dot_product(
+            &query,
+            &data,
+            NumDimensions::from(3),
+            NumVectors::from(2),
+            &mut results,
+        );
+
+        assert_eq!(
+            result,
+            Err(DotProductError::QueryLength {
+                expected: 3,
+                actual: 2
+            })
+        );
+    }
+
+    #[test]
+    fn topk_works() {
+        let query = vec![1., 2., 3.];
+        let data = vec![4., -5., 6., 4., -5., 7., 0., 0., 0., 1., 1., 1.];
+
+        let mut best = ReferenceDotProduct::default().dot_product_topk::<2>(
+            &query,
+            &data,
+            NumDimensions::from(3),
+            NumVectors::from(4),
+        );
+        best.sort_by_key(|entry| entry.index());
+
+        assert_eq!(best[0].index(), 0);
+        assert_eq!(best[0].value(), 12.);
+        assert_eq!(best[1].index(), 1);
+        assert_eq!(best[1].value(), 15.);
+    }
 }
@@ -0,0 +1,140 @@
+use crate::dot_product::{DotProduct, ReferenceDotProduct};
+use abstractions::{NumDimensions, NumVectors};
+use std::arch::x86_64::*;
+
+/// The number of [`f32`] lanes in an AVX2 register.
+const LANES: usize = 8;
+
+/// A dot product using AVX2 fused multiply-add intrinsics.
+///
+/// Falls back to [`ReferenceDotProduct`] if the CPU does not support AVX2 and FMA
+/// at runtime, or if the number of dimensions is not a multiple of eight.
+#[derive(Default)]
+pub struct Avx2DotProduct {}
+
+impl Avx2DotProduct {
+    /// Determines whether the current CPU supports the required instruction sets.
+    pub fn is_supported() -> bool {
+        is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma")
+    }
+}
+
+impl DotProduct for Avx2DotProduct {
+    fn dot_product(
+        &self,
+        query: &[f32],
+        data: &[f32],
+        num_dims: NumDimensions,
+        num_vecs: NumVectors,
+        results: &mut [f32],
+    ) {
+        if !Self::is_supported() || *num_dims % LANES != 0 {
+            return ReferenceDotProduct::default()
+                .dot_product(query, data, num_dims, num_vecs, results);
+        }
+
+        let num_vecs = num_vecs.into_inner();
+        let num_dims = num_dims.into_inner();
+
+        // These are hard assertions since the kernel uses unchecked pointer arithmetic.
+        assert_eq!(query.len(), num_dims, "query vector dimension mismatch");
+        assert_eq!(results.len(), num_vecs, "result vector dimension mismatch");
+        assert_eq!(
+            data.len(),
+            num_vecs * num_dims,
+            "data buffer dimension mismatch"
+        );
+
+        for (v, result) in results.iter_mut().enumerate() {
+            let start_index = v * num_dims;
+            let row = &data[start_index..start_index + num_dims];
+            *result = unsafe { dot_avx2(query, row) };
+        }
+    }
+}
+
+/// Calculates the dot product of two equally sized vectors, eight lanes at a time.
+///
+/// ## Safety
+/// The CPU must support AVX2 and FMA, and the length of both slices must be
+/// a multiple of eight.
+#[target_feature(enable = "avx2,fma")]
+unsafe fn dot_avx2(query: &[f32], row: &[f32]) -> f32 {
+    debug_assert_eq!(query.len(), row.len());
+    debug_assert_eq!(query.len() % LANES, 0);
+
+    let mut sum = _mm256_setzero_ps();
+    for d in (0..query.len()).step_by(LANES) {
+        let q = _mm256_loadu_ps(query.as_ptr().add(d));
+        let r = _mm256_loadu_ps(row.as_ptr().add(d));
+        sum = _mm256_fmadd_ps(q, r, sum);
+    }
+
+    horizontal_sum(sum)
+}
+
+/// Like [`dot_avx2`], but loads the row using aligned loads.
+///
+/// ## Safety
+/// The CPU must support AVX2 and FMA, the length of both slices must be
+/// a multiple of eight, and the row must be aligned to 32 bytes.
+#[target_feature(enable = "avx2,fma")]
+pub(crate) unsafe fn dot_avx2_aligned(query: &[f32], row: &[f32]) -> f32 {
+    debug_assert_eq!(query.len(), row.len());
+    debug_assert_eq!(query.len() % LANES, 0);
+    debug_assert_eq!(row.as_ptr() as usize % 32, 0, "row is misaligned");
+
+    let mut sum = _mm256_setzero_ps();
+    for d in (0..query.len()).step_by(LANES) {
+        let q = _mm256_loadu_ps(query.as_ptr().add(d));
+        let r = _mm256_load_ps(row.as_ptr().add(d));
+        sum = _mm256_fmadd_ps(q, r, sum);
+    }
+
+    horizontal_sum(sum)
+}
+
+/// Reduces the eight lanes of the register to their sum.
+#[inline(always)]
+unsafe fn horizontal_sum(v: __m256) -> f32 {
+    let low = _mm256_castps256_ps128(v);
+    let high = _mm256_extractf128_ps(v, 1);
+    let sum = _mm_add_ps(low, high);
+    let shuf = _mm_movehdup_ps(sum);
+    let sum = _mm_add_ps(sum, shuf);
+    let shuf = _mm_movehl_ps(shuf, sum);
+    _mm_cvtss_f32(_mm_add_ss(sum, shuf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{generate_test_vectors, rmse};
+
+    #[test]
+    fn avx2_works() {
+        let num_dims = 384;
+        let num_vecs = 1024;
+        let (query, data) = generate_test_vectors(num_dims, num_vecs);
+
+        let mut reference = vec![0.0; num_vecs];
+        ReferenceDotProduct::default().dot_product(
+            &query,
+            &data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut reference,
+        );
+
+        let mut results = vec![0.0; num_vecs];
+        Avx2DotProduct::default().dot_product(
+            &query,
+            &data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut results,
+        );
+
+        assert!(rmse(&reference, &results) < 1e-4);
+    }
+}
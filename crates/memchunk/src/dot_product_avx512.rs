@@ -0,0 +1,111 @@
+use crate::dot_product::DotProduct;
+use crate::dot_product_avx2::Avx2DotProduct;
+use abstractions::{NumDimensions, NumVectors};
+use std::arch::x86_64::*;
+
+/// The number of [`f32`] lanes in an AVX-512 register.
+const LANES: usize = 16;
+
+/// A dot product using AVX-512 fused multiply-add intrinsics.
+///
+/// Falls back to [`Avx2DotProduct`] (and from there to the reference implementation)
+/// if the CPU does not support AVX-512F at runtime, or if the number of dimensions
+/// is not a multiple of sixteen.
+///
+/// Rows are loaded unaligned since arbitrary slices may be passed; on the 64-byte
+/// aligned memory of the chunks with SIMD-aligned dimensions this costs nothing.
+#[derive(Default)]
+pub struct Avx512DotProduct {}
+
+impl Avx512DotProduct {
+    /// Determines whether the current CPU supports the required instruction sets.
+    pub fn is_supported() -> bool {
+        is_x86_feature_detected!("avx512f")
+    }
+}
+
+impl DotProduct for Avx512DotProduct {
+    fn dot_product(
+        &self,
+        query: &[f32],
+        data: &[f32],
+        num_dims: NumDimensions,
+        num_vecs: NumVectors,
+        results: &mut [f32],
+    ) {
+        if !Self::is_supported() || !(*num_dims).is_multiple_of(LANES) {
+            return Avx2DotProduct::default().dot_product(query, data, num_dims, num_vecs, results);
+        }
+
+        let num_vecs = num_vecs.into_inner();
+        let num_dims = num_dims.into_inner();
+
+        // These are hard assertions since the kernel uses unchecked pointer arithmetic.
+        assert_eq!(query.len(), num_dims, "query vector dimension mismatch");
+        assert_eq!(results.len(), num_vecs, "result vector dimension mismatch");
+        assert_eq!(
+            data.len(),
+            num_vecs * num_dims,
+            "data buffer dimension mismatch"
+        );
+
+        for (result, row) in results.iter_mut().zip(data.chunks_exact(num_dims)) {
+            // SAFETY: Support for AVX-512F and the dimension multiple were checked above.
+            *result = unsafe { dot_avx512(query, row) };
+        }
+    }
+}
+
+/// Calculates the dot product of two equally sized vectors, sixteen lanes at a time.
+///
+/// ## Safety
+/// The CPU must support AVX-512F, and the length of both slices must be
+/// a multiple of sixteen.
+#[target_feature(enable = "avx512f")]
+unsafe fn dot_avx512(query: &[f32], row: &[f32]) -> f32 {
+    debug_assert_eq!(query.len(), row.len());
+    debug_assert_eq!(query.len() % LANES, 0);
+
+    let mut sum = _mm512_setzero_ps();
+    for d in (0..query.len()).step_by(LANES) {
+        let q = _mm512_loadu_ps(query.as_ptr().add(d));
+        let r = _mm512_loadu_ps(row.as_ptr().add(d));
+        sum = _mm512_fmadd_ps(q, r, sum);
+    }
+
+    _mm512_reduce_add_ps(sum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dot_product::ReferenceDotProduct;
+    use crate::test_utils::{generate_test_vectors, rmse};
+
+    #[test]
+    fn avx512_works() {
+        let num_dims = 384;
+        let num_vecs = 1024;
+        let (query, data) = generate_test_vectors(num_dims, num_vecs);
+
+        let mut reference = vec![0.0; num_vecs];
+        ReferenceDotProduct::default().dot_product(
+            &query,
+            &data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut reference,
+        );
+
+        let mut results = vec![0.0; num_vecs];
+        Avx512DotProduct::default().dot_product(
+            &query,
+            &data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut results,
+        );
+
+        assert!(rmse(&reference, &results) < 1e-4);
+    }
+}
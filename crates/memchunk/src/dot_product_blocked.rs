@@ -0,0 +1,175 @@
+use crate::dot_product::DotProduct;
+use abstractions::{NumDimensions, NumVectors};
+
+/// The default number of bytes of data vectors processed as one tile,
+/// chosen to leave room for the queries in a typical 512 KiB L2 cache.
+pub const DEFAULT_TILE_BYTES: usize = 256 * 1024;
+
+/// The number of independent accumulators per dot product.
+const LANES: usize = 8;
+
+/// A dot product that processes the data vectors in tiles sized to stay in the L2 cache.
+///
+/// For batched queries, every tile is scored against all queries before the next tile
+/// is loaded, so each data vector is read from memory only once. Within a row, the
+/// dimensions are accumulated into independent lanes to shorten the dependency chain.
+pub struct BlockedDotProduct {
+    tile_bytes: usize,
+}
+
+impl BlockedDotProduct {
+    /// Creates a blocked dot product with tiles of approximately `tile_bytes` bytes.
+    ///
+    /// Tiles always hold at least one data vector.
+    pub fn new(tile_bytes: usize) -> Self {
+        Self { tile_bytes }
+    }
+
+    pub fn tile_bytes(&self) -> usize {
+        self.tile_bytes
+    }
+
+    /// The number of data vectors per tile.
+    fn tile_rows(&self, num_dims: usize) -> usize {
+        (self.tile_bytes / (num_dims * std::mem::size_of::<f32>()).max(1)).max(1)
+    }
+}
+
+impl Default for BlockedDotProduct {
+    fn default() -> Self {
+        Self::new(DEFAULT_TILE_BYTES)
+    }
+}
+
+impl DotProduct for BlockedDotProduct {
+    fn dot_product(
+        &self,
+        query: &[f32],
+        data: &[f32],
+        num_dims: NumDimensions,
+        num_vecs: NumVectors,
+        results: &mut [f32],
+    ) {
+        self.dot_product_batch(query, 1.into(), data, num_dims, num_vecs, results);
+    }
+
+    /// Calculates the dot products of multiple queries against all data vectors,
+    /// scoring each tile of data vectors against all queries before moving on.
+    fn dot_product_batch(
+        &self,
+        queries: &[f32],
+        num_queries: NumVectors,
+        data: &[f32],
+        num_dims: NumDimensions,
+        num_vecs: NumVectors,
+        results: &mut [f32],
+    ) {
+        let num_queries = num_queries.into_inner();
+        let num_vecs = num_vecs.into_inner();
+        let num_dims = num_dims.into_inner();
+
+        debug_assert_eq!(
+            queries.len(),
+            num_queries * num_dims,
+            "query matrix dimension mismatch"
+        );
+        debug_assert_eq!(
+            results.len(),
+            num_queries * num_vecs,
+            "result matrix dimension mismatch"
+        );
+        debug_assert_eq!(
+            data.len(),
+            num_vecs * num_dims,
+            "data buffer dimension mismatch"
+        );
+
+        if num_dims == 0 {
+            results.fill(0.0);
+            return;
+        }
+
+        let tile_rows = self.tile_rows(num_dims);
+        for (t, tile) in data.chunks(tile_rows * num_dims).enumerate() {
+            let first_vec = t * tile_rows;
+            for (q, query) in queries.chunks_exact(num_dims).enumerate() {
+                let start = q * num_vecs + first_vec;
+                let results = &mut results[start..start + tile.len() / num_dims];
+                for (result, row) in results.iter_mut().zip(tile.chunks_exact(num_dims)) {
+                    *result = dot_lanes(query, row);
+                }
+            }
+        }
+    }
+}
+
+/// Calculates the dot product of two equally sized vectors using [`LANES`] accumulators.
+#[inline(always)]
+fn dot_lanes(query: &[f32], row: &[f32]) -> f32 {
+    let mut sum = [0.0; LANES];
+    let query_lanes = query.chunks_exact(LANES);
+    let row_lanes = row.chunks_exact(LANES);
+
+    let tail = query_lanes
+        .remainder()
+        .iter()
+        .zip(row_lanes.remainder())
+        .fold(0.0, |sum, (&q, &r)| sum + r * q);
+
+    for (q, r) in query_lanes.zip(row_lanes) {
+        for lane in 0..LANES {
+            sum[lane] += r[lane] * q[lane];
+        }
+    }
+
+    sum.iter().sum::<f32>() + tail
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{generate_test_vectors, rmse};
+    use crate::ReferenceDotProduct;
+
+    #[test]
+    fn blocked_matches_reference() {
+        let num_dims = 100;
+        let num_vecs = 300;
+        let num_queries = 3;
+        let (_, data) = generate_test_vectors(num_dims, num_vecs);
+        let queries = &data[..num_queries * num_dims];
+
+        let mut expected = vec![0.0; num_queries * num_vecs];
+        ReferenceDotProduct::default().dot_product_batch(
+            queries,
+            num_queries.into(),
+            &data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut expected,
+        );
+
+        // A tile of 7 vectors leaves a partial tile at the end.
+        let blocked = BlockedDotProduct::new(7 * num_dims * 4);
+        let mut results = vec![0.0; num_queries * num_vecs];
+        blocked.dot_product_batch(
+            queries,
+            num_queries.into(),
+            &data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut results,
+        );
+        assert!(rmse(&expected, &results) < 1e-5);
+
+        let mut results = vec![0.0; num_vecs];
+        BlockedDotProduct::default().dot_product(
+            &queries[..num_dims],
+            &data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut results,
+        );
+        assert!(rmse(&expected[..num_vecs], &results) < 1e-5);
+    }
+}
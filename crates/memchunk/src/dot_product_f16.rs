@@ -0,0 +1,133 @@
+use abstractions::{NumDimensions, NumVectors};
+use half::f16;
+use rayon::prelude::*;
+
+/// A dot product of an `f32` query against half-precision data vectors.
+///
+/// This mirrors [`DotProduct`](crate::DotProduct), which is fixed to `f32` data.
+pub trait DotProductF16 {
+    /// Calculates the dot products of the query against all data vectors,
+    /// converting each data element to `f32` during accumulation.
+    fn dot_product_f16(
+        &self,
+        query: &[f32],
+        data: &[f16],
+        num_dims: NumDimensions,
+        num_vecs: NumVectors,
+        results: &mut [f32],
+    );
+}
+
+/// Scores half-precision data vectors without materializing an `f32` copy of them.
+#[derive(Default)]
+pub struct F16DotProduct {}
+
+/// Like [`F16DotProduct`], but scores the data vectors in parallel.
+#[derive(Default)]
+pub struct F16DotProductParallel {}
+
+impl DotProductF16 for F16DotProduct {
+    fn dot_product_f16(
+        &self,
+        query: &[f32],
+        data: &[f16],
+        num_dims: NumDimensions,
+        num_vecs: NumVectors,
+        results: &mut [f32],
+    ) {
+        let num_vecs = num_vecs.into_inner();
+        let num_dims = num_dims.into_inner();
+
+        debug_assert_eq!(query.len(), num_dims, "query vector dimension mismatch");
+        debug_assert_eq!(results.len(), num_vecs, "result vector dimension mismatch");
+        debug_assert_eq!(
+            data.len(),
+            num_vecs * num_dims,
+            "data buffer dimension mismatch"
+        );
+
+        for (v, result) in results.iter_mut().enumerate() {
+            *result = dot_f16(query, &data[v * num_dims..(v + 1) * num_dims]);
+        }
+    }
+}
+
+impl DotProductF16 for F16DotProductParallel {
+    fn dot_product_f16(
+        &self,
+        query: &[f32],
+        data: &[f16],
+        num_dims: NumDimensions,
+        num_vecs: NumVectors,
+        results: &mut [f32],
+    ) {
+        let num_vecs = num_vecs.into_inner();
+        let num_dims = num_dims.into_inner();
+
+        debug_assert_eq!(query.len(), num_dims, "query vector dimension mismatch");
+        debug_assert_eq!(results.len(), num_vecs, "result vector dimension mismatch");
+        debug_assert_eq!(
+            data.len(),
+            num_vecs * num_dims,
+            "data buffer dimension mismatch"
+        );
+
+        results.par_iter_mut().enumerate().for_each(|(v, result)| {
+            *result = dot_f16(query, &data[v * num_dims..(v + 1) * num_dims]);
+        });
+    }
+}
+
+#[inline(always)]
+fn dot_f16(query: &[f32], row: &[f16]) -> f32 {
+    query
+        .iter()
+        .zip(row)
+        .fold(0.0, |sum, (&q, &r)| sum + r.to_f32() * q)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{generate_test_vectors, rmse};
+    use crate::{DotProduct, ReferenceDotProduct};
+
+    #[test]
+    fn f16_matches_reference_on_rounded_data() {
+        let num_dims = 384;
+        let num_vecs = 256;
+        let (query, data) = generate_test_vectors(num_dims, num_vecs);
+
+        let half_data: Vec<f16> = data.iter().map(|&x| f16::from_f32(x)).collect();
+        let rounded: Vec<f32> = half_data.iter().map(|x| x.to_f32()).collect();
+
+        let mut expected = vec![0.0; num_vecs];
+        ReferenceDotProduct::default().dot_product(
+            &query,
+            &rounded,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut expected,
+        );
+
+        let mut results = vec![0.0; num_vecs];
+        F16DotProduct::default().dot_product_f16(
+            &query,
+            &half_data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut results,
+        );
+        assert!(rmse(&expected, &results) < 1e-6);
+
+        let mut results = vec![0.0; num_vecs];
+        F16DotProductParallel::default().dot_product_f16(
+            &query,
+            &half_data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut results,
+        );
+        assert!(rmse(&expected, &results) < 1e-6);
+    }
+}
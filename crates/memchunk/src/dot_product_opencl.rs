@@ -0,0 +1,216 @@
+use crate::dot_product::DotProduct;
+use abstractions::{NumDimensions, NumVectors};
+use ocl::{Buffer, Context, Device, Kernel, MemFlags, Platform, Program, Queue};
+use std::sync::Mutex;
+
+/// The OpenCL source of the `dot_product` kernel operating on a column-major matrix.
+pub const OPENCL_DOT_PRODUCT_SOURCE: &str = include_str!("dot_product_opencl.cl");
+
+/// The number of rows processed by a work group of the kernel.
+const X: usize = 16;
+
+/// The number of partial sums per row computed by a work group of the kernel.
+const P: usize = 16;
+
+/// A dot product executed on an OpenCL device.
+///
+/// The data matrix is transposed, uploaded and processed on every call.
+/// Device buffers are kept across calls and only reallocated when the
+/// dimensions of the data change.
+pub struct OpenClDotProduct {
+    queue: Queue,
+    program: Program,
+    buffers: Mutex<Option<Buffers>>,
+}
+
+/// The device buffers and kernel for one data size.
+struct Buffers {
+    num_rows: usize,
+    num_dims: usize,
+    matrix: Buffer<f32>,
+    vector: Buffer<f32>,
+    result: Buffer<f32>,
+    kernel: Kernel,
+}
+
+impl OpenClDotProduct {
+    /// Creates a context for the device and builds the kernel.
+    pub fn new(platform: Platform, device: Device) -> ocl::Result<Self> {
+        let context = Context::builder()
+            .platform(platform)
+            .devices(device)
+            .build()?;
+
+        let program = Program::builder()
+            .devices(device)
+            .src(OPENCL_DOT_PRODUCT_SOURCE)
+            .build(&context)?;
+
+        let queue = Queue::new(&context, device, None)?;
+
+        Ok(Self {
+            queue,
+            program,
+            buffers: Mutex::new(None),
+        })
+    }
+
+    /// Calculates the dot products, returning OpenCL errors instead of panicking.
+    pub fn try_dot_product(
+        &self,
+        query: &[f32],
+        data: &[f32],
+        num_dims: NumDimensions,
+        num_vecs: NumVectors,
+        results: &mut [f32],
+    ) -> ocl::Result<()> {
+        let num_vecs = num_vecs.into_inner();
+        let num_dims = num_dims.into_inner();
+
+        debug_assert_eq!(query.len(), num_dims, "query vector dimension mismatch");
+        debug_assert_eq!(results.len(), num_vecs, "result vector dimension mismatch");
+        debug_assert_eq!(
+            data.len(),
+            num_vecs * num_dims,
+            "data buffer dimension mismatch"
+        );
+
+        // The global work size must be a multiple of the work group size,
+        // so the matrix is padded with zero rows.
+        let num_rows = (num_vecs + X - 1) / X * X;
+        if num_rows == 0 {
+            return Ok(());
+        }
+
+        let mut transposed = vec![0.0; num_rows * num_dims];
+        for (v, row) in data.chunks_exact(num_dims).enumerate() {
+            for (d, &value) in row.iter().enumerate() {
+                transposed[d * num_rows + v] = value;
+            }
+        }
+
+        let mut buffers = self.buffers.lock().expect("buffer lock poisoned");
+        let buffers = match buffers.as_mut() {
+            Some(buffers) if buffers.num_rows == num_rows && buffers.num_dims == num_dims => {
+                buffers
+            }
+            _ => buffers.insert(self.create_buffers(num_rows, num_dims)?),
+        };
+
+        buffers.matrix.cmd().write(&transposed).enq()?;
+        buffers.vector.cmd().write(query).enq()?;
+        unsafe { buffers.kernel.cmd().enq()? };
+
+        let mut padded_results = vec![f32::NAN; num_rows];
+        buffers.result.cmd().read(&mut padded_results).enq()?;
+        self.queue.finish()?;
+
+        results.copy_from_slice(&padded_results[..num_vecs]);
+        Ok(())
+    }
+
+    fn create_buffers(&self, num_rows: usize, num_dims: usize) -> ocl::Result<Buffers> {
+        let matrix = Buffer::<f32>::builder()
+            .queue(self.queue.clone())
+            .flags(MemFlags::new().read_only().host_write_only())
+            .len(num_rows * num_dims)
+            .build()?;
+
+        let vector = Buffer::<f32>::builder()
+            .queue(self.queue.clone())
+            .flags(MemFlags::new().read_only().host_write_only())
+            .len(num_dims)
+            .build()?;
+
+        let result = Buffer::<f32>::builder()
+            .queue(self.queue.clone())
+            .flags(MemFlags::new().write_only().host_read_only())
+            .len(num_rows)
+            .build()?;
+
+        let kernel = Kernel::builder()
+            .program(&self.program)
+            .name("dot_product")
+            .queue(self.queue.clone())
+            .global_work_size([num_rows, P])
+            .local_work_size([X, P])
+            .arg(&matrix)
+            .arg(&vector)
+            .arg(&result)
+            .arg_local::<f32>(X * (P + 1))
+            .arg(num_rows as u32)
+            .arg(num_dims as u32)
+            .build()?;
+
+        Ok(Buffers {
+            num_rows,
+            num_dims,
+            matrix,
+            vector,
+            result,
+            kernel,
+        })
+    }
+}
+
+impl DotProduct for OpenClDotProduct {
+    /// Calculates the dot products on the device.
+    ///
+    /// ## Panics
+    /// Panics if an OpenCL operation fails; see [`OpenClDotProduct::try_dot_product`]
+    /// for a fallible alternative.
+    fn dot_product(
+        &self,
+        query: &[f32],
+        data: &[f32],
+        num_dims: NumDimensions,
+        num_vecs: NumVectors,
+        results: &mut [f32],
+    ) {
+        self.try_dot_product(query, data, num_dims, num_vecs, results)
+            .expect("OpenCL dot product failed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{generate_test_vectors, rmse};
+    use crate::ReferenceDotProduct;
+
+    #[test]
+    fn opencl_works() {
+        let Some(platform) = Platform::list().into_iter().next() else {
+            return;
+        };
+        let Ok(device) = Device::first(platform) else {
+            return;
+        };
+
+        let num_dims = 384;
+        let num_vecs = 1000;
+        let (query, data) = generate_test_vectors(num_dims, num_vecs);
+
+        let mut reference = vec![0.0; num_vecs];
+        ReferenceDotProduct::default().dot_product(
+            &query,
+            &data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut reference,
+        );
+
+        let dot_product = OpenClDotProduct::new(platform, device).unwrap();
+        let mut results = vec![0.0; num_vecs];
+        for _ in 0..2 {
+            dot_product.dot_product(
+                &query,
+                &data,
+                num_dims.into(),
+                num_vecs.into(),
+                &mut results,
+            );
+            assert!(rmse(&reference, &results) < 1e-4);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use abstractions::{NumDimensions, NumVectors};
+
+/// A dot product of a sparse query against dense data vectors.
+///
+/// The query is given by the indices and values of its nonzero dimensions, so only
+/// those dimensions of each data vector are gathered instead of scanning all of them.
+/// The results are the same as those of [`DotProduct`](crate::DotProduct) for the
+/// densified query, up to rounding.
+#[derive(Default)]
+pub struct SparseDotProduct {}
+
+impl SparseDotProduct {
+    /// Calculates the dot products of the sparse query against all data vectors.
+    ///
+    /// ## Arguments
+    /// * `indices` - The dimensions of the nonzero query values; each must be less than `num_dims`.
+    /// * `values` - The query values, one per index.
+    /// * `data` - The row-major `num_vecs × num_dims` data matrix.
+    /// * `results` - The score of each data vector.
+    pub fn dot_product(
+        &self,
+        indices: &[u32],
+        values: &[f32],
+        data: &[f32],
+        num_dims: NumDimensions,
+        num_vecs: NumVectors,
+        results: &mut [f32],
+    ) {
+        let num_vecs = num_vecs.into_inner();
+        let num_dims = num_dims.into_inner();
+
+        assert_eq!(indices.len(), values.len(), "query length mismatch");
+        assert!(
+            indices.iter().all(|&index| (index as usize) < num_dims),
+            "query index out of bounds"
+        );
+        debug_assert_eq!(results.len(), num_vecs, "result vector dimension mismatch");
+        debug_assert_eq!(
+            data.len(),
+            num_vecs * num_dims,
+            "data buffer dimension mismatch"
+        );
+
+        for (result, row) in results.iter_mut().zip(data.chunks_exact(num_dims)) {
+            *result = indices
+                .iter()
+                .zip(values)
+                .fold(0.0, |sum, (&index, &value)| {
+                    sum + row[index as usize] * value
+                });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::generate_test_vectors;
+    use crate::{DotProduct, ReferenceDotProduct};
+
+    #[test]
+    fn sparse_matches_densified_reference() {
+        let num_dims = 1000;
+        let num_vecs = 64;
+        let (_, data) = generate_test_vectors(num_dims, num_vecs);
+
+        let indices = [3, 17, 512, 998, 999];
+        let values = [0.5, -1.25, 2.0, 0.125, -0.75];
+        let mut query = vec![0.0; num_dims];
+        for (&index, &value) in indices.iter().zip(&values) {
+            query[index as usize] = value;
+        }
+
+        let mut expected = vec![0.0; num_vecs];
+        ReferenceDotProduct::default().dot_product(
+            &query,
+            &data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut expected,
+        );
+
+        let mut results = vec![f32::NAN; num_vecs];
+        SparseDotProduct::default().dot_product(
+            &indices,
+            &values,
+            &data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut results,
+        );
+
+        for (&expected, &result) in expected.iter().zip(&results) {
+            assert!((expected - result).abs() < 1e-4);
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use crate::dot_product::DotProduct;
+use abstractions::{NumDimensions, NumVectors};
+
+/// The number of dimensions accumulated between two early-termination checks.
+const CHECK_INTERVAL: usize = 16;
+
+/// The slack added to the upper bound of a row's score to absorb rounding errors,
+/// so that rows are never abandoned if they could reach the threshold.
+const BOUND_TOLERANCE: f32 = 1e-5;
+
+/// A dot product that only reports scores of at least `min_score`.
+///
+/// Rows scoring below the threshold are reported as [`f32::NEG_INFINITY`].
+///
+/// If constructed with [`ThresholdedDotProduct::new_normalized`], the query and all
+/// data vectors must have unit length. The scan of a row is then abandoned as soon
+/// as the Cauchy–Schwarz bound `‖q_rest‖ · ‖r_rest‖` on the remaining dimensions
+/// shows that the threshold cannot be reached, where `‖r_rest‖² = 1 - ‖r_seen‖²`.
+/// Results are meaningless if the vectors are not normalized.
+///
+/// With [`ThresholdedDotProduct::new`] no assumption is made about the data and
+/// every row is scanned completely before the threshold is applied.
+pub struct ThresholdedDotProduct {
+    min_score: f32,
+    normalized: bool,
+}
+
+impl ThresholdedDotProduct {
+    /// Creates a thresholded dot product without early termination.
+    pub fn new(min_score: f32) -> Self {
+        Self {
+            min_score,
+            normalized: false,
+        }
+    }
+
+    /// Creates a thresholded dot product for unit-length vectors that
+    /// terminates early on rows that cannot reach the threshold.
+    pub fn new_normalized(min_score: f32) -> Self {
+        Self {
+            min_score,
+            normalized: true,
+        }
+    }
+
+    pub fn min_score(&self) -> f32 {
+        self.min_score
+    }
+
+    /// Calculates the score of a row, returning `None` if the row was abandoned.
+    ///
+    /// ## Arguments
+    /// * `rest_norms` - The norm of the query's dimensions following each check interval.
+    fn bounded_dot(&self, query: &[f32], row: &[f32], rest_norms: &[f32]) -> Option<f32> {
+        let mut sum = 0.0;
+        let mut row_norm_sq = 0.0;
+
+        for ((query, row), &rest_norm) in query
+            .chunks(CHECK_INTERVAL)
+            .zip(row.chunks(CHECK_INTERVAL))
+            .zip(rest_norms)
+        {
+            for (&q, &r) in query.iter().zip(row) {
+                sum += q * r;
+                row_norm_sq += r * r;
+            }
+
+            let row_rest_norm = (1.0 - row_norm_sq).max(0.0).sqrt();
+            let bound = sum + rest_norm * row_rest_norm + BOUND_TOLERANCE;
+            if bound < self.min_score {
+                return None;
+            }
+        }
+
+        Some(sum)
+    }
+}
+
+impl DotProduct for ThresholdedDotProduct {
+    fn dot_product(
+        &self,
+        query: &[f32],
+        data: &[f32],
+        num_dims: NumDimensions,
+        num_vecs: NumVectors,
+        results: &mut [f32],
+    ) {
+        let num_vecs = num_vecs.into_inner();
+        let num_dims = num_dims.into_inner();
+
+        debug_assert_eq!(query.len(), num_dims, "query vector dimension mismatch");
+        debug_assert_eq!(results.len(), num_vecs, "result vector dimension mismatch");
+        debug_assert_eq!(
+            data.len(),
+            num_vecs * num_dims,
+            "data buffer dimension mismatch"
+        );
+
+        if !self.normalized {
+            for (result, row) in results.iter_mut().zip(data.chunks_exact(num_dims)) {
+                let sum = query.iter().zip(row).fold(0.0, |sum, (&q, &r)| sum + r * q);
+                *result = if sum >= self.min_score {
+                    sum
+                } else {
+                    f32::NEG_INFINITY
+                };
+            }
+            return;
+        }
+
+        let rest_norms = query_rest_norms(query);
+        for (result, row) in results.iter_mut().zip(data.chunks_exact(num_dims)) {
+            *result = match self.bounded_dot(query, row, &rest_norms) {
+                Some(sum) if sum >= self.min_score => sum,
+                _ => f32::NEG_INFINITY,
+            };
+        }
+    }
+}
+
+/// Calculates the norm of the query's dimensions following each check interval.
+fn query_rest_norms(query: &[f32]) -> Vec<f32> {
+    let mut rest_norms: Vec<f32> = query
+        .chunks(CHECK_INTERVAL)
+        .rev()
+        .scan(0.0f32, |rest_sq, chunk| {
+            let norm = rest_sq.sqrt();
+            *rest_sq += chunk.iter().map(|q| q * q).sum::<f32>();
+            Some(norm)
+        })
+        .collect();
+    rest_norms.reverse();
+    rest_norms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::generate_test_vectors;
+    use crate::ReferenceDotProduct;
+
+    fn normalize(values: &mut [f32], num_dims: usize) {
+        for row in values.chunks_exact_mut(num_dims) {
+            let norm = row.iter().map(|x| x * x).sum::<f32>().sqrt();
+            row.iter_mut().for_each(|x| *x /= norm);
+        }
+    }
+
+    #[test]
+    fn thresholded_matches_reference_above_threshold() {
+        let num_dims = 100;
+        let num_vecs = 256;
+        let (mut query, mut data) = generate_test_vectors(num_dims, num_vecs);
+        normalize(&mut query, num_dims);
+        normalize(&mut data, num_dims);
+
+        let mut expected = vec![0.0; num_vecs];
+        ReferenceDotProduct::default().dot_product(
+            &query,
+            &data,
+            num_dims.into(),
+            num_vecs.into(),
+            &mut expected,
+        );
+
+        let min_score = 0.1;
+        for dot_product in [
+            ThresholdedDotProduct::new(min_score),
+            ThresholdedDotProduct::new_normalized(min_score),
+        ] {
+            let mut results = vec![0.0; num_vecs];
+            dot_product.dot_product(
+                &query,
+                &data,
+                num_dims.into(),
+                num_vecs.into(),
+                &mut results,
+            );
+
+            for (&expected, &result) in expected.iter().zip(&results) {
+                if expected >= min_score {
+                    assert!((expected - result).abs() < 1e-5);
+                } else {
+                    assert_eq!(result, f32::NEG_INFINITY);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn query_rest_norms_works() {
+        let query = [1.0; 40];
+        let rest_norms = query_rest_norms(&query);
+        assert_eq!(rest_norms.len(), 3);
+        assert!((rest_norms[0] - 24f32.sqrt()).abs() < 1e-6);
+        assert!((rest_norms[1] - 8f32.sqrt()).abs() < 1e-6);
+        assert_eq!(rest_norms[2], 0.0);
+    }
+}
@@ -1,4 +1,9 @@
-use alloc_madvise::Memory;
+use crate::dot_product::{DotProduct, ReferenceDotProduct};
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+use crate::dot_product_avx2::{dot_avx2_aligned, Avx2DotProduct};
+use abstractions::{NumDimensions, NumVectors};
+use alloc_madvise::{AllocationError as AllocError, Memory};
+use std::fmt::{Debug, Display, Formatter};
 use std::ops::{Deref, DerefMut};
 
 /// The number of bytes in a memory chunk.
@@ -10,13 +15,12 @@ pub const CHUNK_NUM_FLOATS: usize = CHUNK_SIZE_BYTES / std::mem::size_of::<f32>(
 /// A slice of [`f32`] of exactly [`CHUNK_NUM_FLOATS`] elements.
 pub type ChunkTypeF32 = [f32; CHUNK_NUM_FLOATS];
 
-#[derive(Debug)]
 pub struct FixedSizeMemoryChunk {
     data: Memory,
 }
 
 /// Hints at the intended memory access pattern.
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum AccessHint {
     /// Memory access will be mostly or entirely sequential.
     Seqential,
@@ -30,6 +34,19 @@ impl Default for AccessHint {
     }
 }
 
+/// Hints at where the physical memory pages of a chunk should be placed.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum AllocationHint {
+    /// Pages are placed by the operating system when they are first accessed.
+    #[default]
+    Default,
+    /// Pages are faulted in by the allocating thread right away, so that on NUMA
+    /// systems they are placed on the memory node of that thread.
+    ///
+    /// To keep data local to worker threads, allocate the chunks on the workers.
+    FirstTouch,
+}
+
 impl FixedSizeMemoryChunk {
     /// The number of bytes in this memory chunk.
     pub const SIZE_BYTES: usize = CHUNK_SIZE_BYTES;
@@ -37,12 +54,99 @@ impl FixedSizeMemoryChunk {
     /// The number of [`f32`] elements in this memory chunk.
     pub const LENGTH: usize = CHUNK_NUM_FLOATS;
 
+    /// The guaranteed minimum alignment of the chunk's memory in bytes.
+    pub const ALIGNMENT: usize = 64;
+
+    /// Allocates a zeroed chunk.
+    ///
+    /// ## Panics
+    /// Panics if the memory could not be allocated; see [`FixedSizeMemoryChunk::try_allocate`]
+    /// for a fallible alternative.
     pub fn allocate(access_pattern: AccessHint) -> Self {
+        Self::try_allocate(access_pattern).expect("memory allocation failed")
+    }
+
+    /// Allocates a zeroed chunk, returning an error if the memory could not be allocated.
+    pub fn try_allocate(access_pattern: AccessHint) -> Result<Self, AllocError> {
+        Self::try_allocate_with_hints(access_pattern, AllocationHint::Default)
+    }
+
+    /// Allocates a zeroed chunk with the specified page placement,
+    /// returning an error if the memory could not be allocated.
+    pub fn try_allocate_with_hints(
+        access_pattern: AccessHint,
+        placement: AllocationHint,
+    ) -> Result<Self, AllocError> {
         let sequential = access_pattern == AccessHint::Seqential;
-        let chunk =
-            Memory::allocate(Self::SIZE_BYTES, sequential, true).expect("memory allocation failed");
+        let mut chunk = Memory::allocate(Self::SIZE_BYTES, sequential, true)?;
+        if placement == AllocationHint::FirstTouch {
+            touch_pages(&mut chunk);
+        }
+
+        Ok(Self { data: chunk })
+    }
 
-        Self { data: chunk }
+    /// Returns a pointer to the first element of the chunk, e.g. for passing it to FFI.
+    ///
+    /// The pointer is aligned to at least [`FixedSizeMemoryChunk::ALIGNMENT`] bytes and valid
+    /// for reads of [`FixedSizeMemoryChunk::LENGTH`] elements while the chunk is borrowed.
+    pub fn as_aligned_ptr(&self) -> *const f32 {
+        let data: &[f32] = self.data.as_ref();
+        let ptr = data.as_ptr();
+        debug_assert_eq!(ptr as usize % Self::ALIGNMENT, 0, "chunk is misaligned");
+        ptr
+    }
+
+    /// Returns a mutable pointer to the first element of the chunk, e.g. for passing it to FFI.
+    ///
+    /// The pointer is aligned to at least [`FixedSizeMemoryChunk::ALIGNMENT`] bytes and valid
+    /// for reads and writes of [`FixedSizeMemoryChunk::LENGTH`] elements while the chunk is
+    /// mutably borrowed.
+    pub fn as_aligned_mut_ptr(&mut self) -> *mut f32 {
+        let data: &mut [f32] = self.data.as_mut();
+        let ptr = data.as_mut_ptr();
+        debug_assert_eq!(ptr as usize % Self::ALIGNMENT, 0, "chunk is misaligned");
+        ptr
+    }
+
+    /// The number of complete rows of `num_dims` dimensions that fit into a chunk.
+    pub fn num_rows(num_dims: NumDimensions) -> NumVectors {
+        (Self::LENGTH / *num_dims).into()
+    }
+
+    /// Calculates the dot products of the query against all rows of the chunk,
+    /// interpreting it as a row-major matrix of [`FixedSizeMemoryChunk::num_rows`] rows.
+    ///
+    /// Since the chunk is aligned to [`FixedSizeMemoryChunk::ALIGNMENT`] bytes, every row
+    /// is SIMD-aligned if the number of dimensions is a multiple of eight; the rows are
+    /// then scored using aligned AVX2 loads where supported.
+    ///
+    /// ## Panics
+    /// Panics if `num_dims` is zero or exceeds [`FixedSizeMemoryChunk::LENGTH`], or if the
+    /// query or results do not hold `num_dims` and one value per row, respectively.
+    pub fn dot_product_rows(&self, query: &[f32], num_dims: NumDimensions, results: &mut [f32]) {
+        assert!(
+            *num_dims > 0 && *num_dims <= Self::LENGTH,
+            "invalid number of dimensions"
+        );
+        let num_rows = Self::num_rows(num_dims);
+        assert_eq!(query.len(), *num_dims, "query vector dimension mismatch");
+        assert_eq!(results.len(), *num_rows, "result vector dimension mismatch");
+
+        let data: &[f32] = self.data.as_ref();
+        let data = &data[..*num_rows * *num_dims];
+
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        if Avx2DotProduct::is_supported() && *num_dims % 8 == 0 {
+            for (result, row) in results.iter_mut().zip(data.chunks_exact(*num_dims)) {
+                // SAFETY: Instruction support and the dimension multiple were checked above;
+                // rows start at multiples of 32 bytes into the 64-byte aligned chunk.
+                *result = unsafe { dot_avx2_aligned(query, row) };
+            }
+            return;
+        }
+
+        ReferenceDotProduct::default().dot_product(query, data, num_dims, num_rows, results);
     }
 
     pub const fn len(&self) -> usize {
@@ -54,8 +158,20 @@ impl FixedSizeMemoryChunk {
     }
 }
 
-trait DotProduct<const NUM_FLOATS: usize> {
-    fn dot_product(coeffs: [f32; NUM_FLOATS]);
+/// Prints the size of the chunk, but not its payload.
+impl Debug for FixedSizeMemoryChunk {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FixedSizeMemoryChunk")
+            .field("len", &Self::LENGTH)
+            .field("bytes", &Self::SIZE_BYTES)
+            .finish()
+    }
+}
+
+impl Display for FixedSizeMemoryChunk {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} floats ({} bytes)", Self::LENGTH, Self::SIZE_BYTES)
+    }
 }
 
 impl Deref for FixedSizeMemoryChunk {
@@ -100,6 +216,30 @@ impl AsMut<ChunkTypeF32> for FixedSizeMemoryChunk {
     }
 }
 
+/// Returns the size of a memory page in bytes.
+pub(crate) fn page_size() -> usize {
+    #[cfg(unix)]
+    {
+        let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if size > 0 {
+            return size as usize;
+        }
+    }
+
+    4096
+}
+
+/// Faults in all pages of the memory on the calling thread by zeroing one byte per page.
+///
+/// This must only be used on memory that is zeroed anyway.
+pub(crate) fn touch_pages(memory: &mut Memory) {
+    let bytes: &mut [u8] = memory.as_mut();
+    for byte in bytes.iter_mut().step_by(page_size()) {
+        // SAFETY: The reference is valid; the volatile write keeps the store from being elided.
+        unsafe { std::ptr::write_volatile(byte, 0) };
+    }
+}
+
 /// Converts from megabytes to bytes.
 ///
 /// ## Arguments
@@ -111,9 +251,55 @@ const fn megabytes_to_bytes(mb: usize) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::{generate_test_vectors, rmse};
 
     #[test]
     fn megabytes_to_bytes_works() {
         assert_eq!(megabytes_to_bytes(1), 1_048_576);
     }
+
+    #[test]
+    fn aligned_pointers_are_aligned() {
+        let mut chunk = FixedSizeMemoryChunk::allocate(AccessHint::Random);
+        let ptr = chunk.as_aligned_ptr();
+        assert_eq!(ptr as usize % FixedSizeMemoryChunk::ALIGNMENT, 0);
+        assert_eq!(chunk.as_aligned_mut_ptr() as *const f32, ptr);
+    }
+
+    #[test]
+    fn dot_product_rows_works() {
+        // 8 dimensions take the aligned SIMD path if available, 7 do not.
+        for num_dims in [8, 7] {
+            let num_rows = *FixedSizeMemoryChunk::num_rows(num_dims.into());
+            let (query, data) = generate_test_vectors(num_dims, num_rows);
+
+            let mut chunk = FixedSizeMemoryChunk::allocate(AccessHint::Seqential);
+            let chunk_data: &mut [f32] = chunk.as_mut();
+            chunk_data[..data.len()].copy_from_slice(&data);
+
+            let mut expected = vec![0.0; num_rows];
+            ReferenceDotProduct::default().dot_product(
+                &query,
+                &data,
+                num_dims.into(),
+                num_rows.into(),
+                &mut expected,
+            );
+
+            let mut results = vec![0.0; num_rows];
+            chunk.dot_product_rows(&query, num_dims.into(), &mut results);
+            assert!(rmse(&expected, &results) < 1e-5);
+        }
+    }
+
+    #[test]
+    fn first_touch_allocation_is_zeroed() {
+        let chunk = FixedSizeMemoryChunk::try_allocate_with_hints(
+            AccessHint::Random,
+            AllocationHint::FirstTouch,
+        )
+        .unwrap();
+        let data: &[f32] = chunk.as_ref();
+        assert!(data.iter().all(|&x| x == 0.0));
+    }
 }
@@ -1,10 +1,63 @@
 mod any_size_memory_chunk;
+mod any_size_memory_chunk_i8;
+mod centroid;
+mod chunk_storage;
+mod cosine_index;
+mod cpu_features;
 mod dot_product;
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod dot_product_avx2;
+// The AVX-512 intrinsics were stabilized in Rust 1.89.
+#[cfg(all(feature = "avx512", target_arch = "x86_64"))]
+#[clippy::msrv = "1.89"]
+mod dot_product_avx512;
+mod dot_product_blocked;
+#[cfg(feature = "half")]
+mod dot_product_f16;
+#[cfg(feature = "opencl")]
+mod dot_product_opencl;
+mod dot_product_sparse;
+mod dot_product_thresholded;
 mod fixed_size_memory_chunk;
 mod memory_view;
+mod metric;
+mod scores;
+#[cfg(test)]
+mod test_utils;
 mod topk;
 
-pub use any_size_memory_chunk::AnySizeMemoryChunk;
+pub use alloc_madvise::AllocationError as AllocError;
+pub use any_size_memory_chunk::{
+    AnySizeMemoryChunk, AnySizeMemoryChunkBuilder, ChunkError, Layout, TRANSPOSE_TILE_SIZE,
+};
+pub use any_size_memory_chunk_i8::AnySizeMemoryChunkI8;
+pub use centroid::Centroid;
+pub use cosine_index::CosineIndex;
+pub use cpu_features::detected_features;
 pub use dot_product::{
-    DotProduct, ReferenceDotProduct, ReferenceDotProductParallel, ReferenceDotProductUnrolled,
+    DotProduct, DotProductError, ReferenceDotProduct, ReferenceDotProductParallel,
+    ReferenceDotProductUnrolled, PROGRESS_BLOCK_ROWS,
+};
+pub use dot_product_blocked::{BlockedDotProduct, DEFAULT_TILE_BYTES};
+pub use dot_product_sparse::SparseDotProduct;
+pub use dot_product_thresholded::ThresholdedDotProduct;
+pub use fixed_size_memory_chunk::{AccessHint, AllocationHint, FixedSizeMemoryChunk};
+pub use memory_view::RowMajorMatrixView;
+pub use metric::{compute_metric, Metric, ParseMetricError};
+pub use scores::{apply_temperature, rmse, softmax_in_place};
+pub use topk::{
+    bottomk, merge_topk, topk, topk_approx, topk_sorted, Entry, EntryT, TopK, TopKAccumulator,
+    TopKValue,
 };
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+pub use dot_product_avx2::Avx2DotProduct;
+
+#[cfg(all(feature = "avx512", target_arch = "x86_64"))]
+pub use dot_product_avx512::Avx512DotProduct;
+
+#[cfg(feature = "half")]
+pub use dot_product_f16::{DotProductF16, F16DotProduct, F16DotProductParallel};
+
+#[cfg(feature = "opencl")]
+pub use dot_product_opencl::{OpenClDotProduct, OPENCL_DOT_PRODUCT_SOURCE};
@@ -38,15 +38,39 @@ impl<const COLS: usize> RowMajorMatrixView<COLS> {
         false
     }
 
-    /*
+    /// Returns the row at the specified index.
+    ///
+    /// ## Panics
+    /// Panics if `i` is not less than [`RowMajorMatrixView::ROWS`].
     #[inline(always)]
-    pub fn to_array(&self) -> &[f32] {
+    pub fn row(&self, i: usize) -> &[f32; COLS] {
+        assert!(i < Self::ROWS, "row index out of bounds");
         let data: &[f32] = self.memory.as_ref();
-        const LENGTH: usize = Self::LENGTH;
-        let data: &[f32; Self::LENGTH] = data.try_into().expect("invalid dimensions");
-        todo!()
+        data[i * COLS..(i + 1) * COLS]
+            .try_into()
+            .expect("invalid dimensions")
+    }
+
+    /// Returns the row at the specified index, mutably.
+    ///
+    /// ## Panics
+    /// Panics if `i` is not less than [`RowMajorMatrixView::ROWS`].
+    #[inline(always)]
+    pub fn row_mut(&mut self, i: usize) -> &mut [f32; COLS] {
+        assert!(i < Self::ROWS, "row index out of bounds");
+        let data: &mut [f32] = self.memory.as_mut();
+        (&mut data[i * COLS..(i + 1) * COLS])
+            .try_into()
+            .expect("invalid dimensions")
+    }
+
+    /// Iterates all rows in order.
+    pub fn iter_rows(&self) -> impl ExactSizeIterator<Item = &[f32; COLS]> {
+        let data: &[f32] = self.memory.as_ref();
+        data[..Self::LENGTH]
+            .chunks_exact(COLS)
+            .map(|row| row.try_into().expect("invalid dimensions"))
     }
-     */
 }
 
 #[cfg(test)]
@@ -66,6 +90,27 @@ mod tests {
         assert_eq!(view.rows(), 21845);
     }
 
+    #[test]
+    fn row_access_works() {
+        let chunk = FixedSizeMemoryChunk::allocate(AccessHint::Seqential);
+        let mut view = RowMajorMatrixView::<384>::wrap(chunk);
+
+        view.row_mut(1)[383] = 1.0;
+        assert_eq!(view.row(1)[383], 1.0);
+        assert_eq!(view.row(2)[0], 0.0);
+
+        assert_eq!(view.iter_rows().len(), view.rows());
+        assert_eq!(view.iter_rows().nth(1), Some(view.row(1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "row index out of bounds")]
+    fn row_access_is_bounds_checked() {
+        let chunk = FixedSizeMemoryChunk::allocate(AccessHint::Seqential);
+        let view = RowMajorMatrixView::<384>::wrap(chunk);
+        view.row(RowMajorMatrixView::<384>::ROWS);
+    }
+
     #[test]
     fn wait_what() {
         let vec = vec![1, 2, 3];
@@ -0,0 +1,187 @@
+use crate::dot_product::{DotProduct, ReferenceDotProduct, ReferenceDotProductParallel};
+use abstractions::{NumDimensions, NumVectors};
+use rayon::prelude::*;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The metric calculated between a query and the data vectors, selectable at runtime.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Metric {
+    /// The inner product.
+    Dot,
+    /// The cosine similarity; zero for vectors without magnitude.
+    Cosine,
+    /// The squared Euclidean distance.
+    EuclideanSq,
+}
+
+impl Metric {
+    /// The names accepted by [`Metric::from_str`].
+    pub const NAMES: [&'static str; 3] = ["dot", "cosine", "euclidean-sq"];
+}
+
+/// The error returned by [`Metric::from_str`] for unknown metric names.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseMetricError {
+    name: String,
+}
+
+impl Display for ParseMetricError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "unknown metric '{name}', expected one of {names}",
+            name = self.name,
+            names = Metric::NAMES.join(", ")
+        )
+    }
+}
+
+impl Error for ParseMetricError {}
+
+impl FromStr for Metric {
+    type Err = ParseMetricError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(Metric::Dot),
+            "cosine" => Ok(Metric::Cosine),
+            "euclidean-sq" => Ok(Metric::EuclideanSq),
+            _ => Err(ParseMetricError { name: s.into() }),
+        }
+    }
+}
+
+/// Calculates the metric between the query and all data vectors,
+/// using the parallel implementations if `parallel` is set.
+pub fn compute_metric(
+    metric: Metric,
+    query: &[f32],
+    data: &[f32],
+    num_dims: NumDimensions,
+    num_vecs: NumVectors,
+    results: &mut [f32],
+    parallel: bool,
+) {
+    debug_assert_eq!(query.len(), *num_dims, "query vector dimension mismatch");
+    debug_assert_eq!(results.len(), *num_vecs, "result vector dimension mismatch");
+    debug_assert_eq!(
+        data.len(),
+        *num_vecs * *num_dims,
+        "data buffer dimension mismatch"
+    );
+
+    match (metric, parallel) {
+        (Metric::Dot, false) => {
+            ReferenceDotProduct::default().dot_product(query, data, num_dims, num_vecs, results)
+        }
+        (Metric::Dot, true) => ReferenceDotProductParallel::default()
+            .dot_product(query, data, num_dims, num_vecs, results),
+        (Metric::Cosine, parallel) => {
+            let query_norm = l2_norm(query);
+            let score = |(result, row): (&mut f32, &[f32])| {
+                *result = cosine_similarity(query, query_norm, row)
+            };
+            for_each_row(data, num_dims, results, parallel, score);
+        }
+        (Metric::EuclideanSq, parallel) => {
+            let score =
+                |(result, row): (&mut f32, &[f32])| *result = euclidean_distance_sq(query, row);
+            for_each_row(data, num_dims, results, parallel, score);
+        }
+    }
+}
+
+/// Applies the scoring function to each pair of result and data vector.
+fn for_each_row<F>(
+    data: &[f32],
+    num_dims: NumDimensions,
+    results: &mut [f32],
+    parallel: bool,
+    score: F,
+) where
+    F: Fn((&mut f32, &[f32])) + Sync + Send,
+{
+    if *num_dims == 0 {
+        results.fill(0.0);
+        return;
+    }
+
+    if parallel {
+        results
+            .par_iter_mut()
+            .zip(data.par_chunks_exact(*num_dims))
+            .for_each(score);
+    } else {
+        results
+            .iter_mut()
+            .zip(data.chunks_exact(*num_dims))
+            .for_each(score);
+    }
+}
+
+#[inline(always)]
+fn l2_norm(vec: &[f32]) -> f32 {
+    vec.iter().fold(0.0, |sum, &x| sum + x * x).sqrt()
+}
+
+#[inline(always)]
+fn cosine_similarity(query: &[f32], query_norm: f32, row: &[f32]) -> f32 {
+    let dot = query.iter().zip(row).fold(0.0, |sum, (&q, &r)| sum + r * q);
+    let norm = query_norm * l2_norm(row);
+    if norm == 0.0 {
+        0.0
+    } else {
+        dot / norm
+    }
+}
+
+#[inline(always)]
+fn euclidean_distance_sq(query: &[f32], row: &[f32]) -> f32 {
+    query
+        .iter()
+        .zip(row)
+        .fold(0.0, |sum, (&q, &r)| sum + (q - r) * (q - r))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_works() {
+        for name in Metric::NAMES {
+            assert!(name.parse::<Metric>().is_ok());
+        }
+        assert_eq!("cosine".parse::<Metric>(), Ok(Metric::Cosine));
+        assert!("manhattan".parse::<Metric>().is_err());
+    }
+
+    #[test]
+    fn compute_metric_works() {
+        let query = [1.0, 0.0];
+        let data = [2.0, 0.0, 0.0, 3.0, 0.0, 0.0];
+        let cases = [
+            (Metric::Dot, [2.0, 0.0, 0.0]),
+            (Metric::Cosine, [1.0, 0.0, 0.0]),
+            (Metric::EuclideanSq, [1.0, 10.0, 1.0]),
+        ];
+
+        for (metric, expected) in cases {
+            for parallel in [false, true] {
+                let mut results = [f32::NAN; 3];
+                compute_metric(
+                    metric,
+                    &query,
+                    &data,
+                    2.into(),
+                    3.into(),
+                    &mut results,
+                    parallel,
+                );
+                assert_eq!(results, expected, "{metric:?}, parallel: {parallel}");
+            }
+        }
+    }
+}
@@ -0,0 +1,88 @@
+/// Divides all scores by the specified temperature.
+///
+/// Temperatures below `1.0` sharpen the distribution produced by a subsequent
+/// [`softmax_in_place`], temperatures above `1.0` flatten it.
+///
+/// ## Arguments
+/// * `scores` - The scores to scale, e.g. the `results` buffer of a dot product.
+/// * `temperature` - The strictly positive temperature.
+pub fn apply_temperature(scores: &mut [f32], temperature: f32) {
+    debug_assert!(temperature > 0.0, "temperature must be positive");
+    let inv_temperature = 1.0 / temperature;
+    for score in scores.iter_mut() {
+        *score *= inv_temperature;
+    }
+}
+
+/// Calculates the root-mean-square error between two equally sized slices,
+/// e.g. to compare scores against a reference implementation.
+///
+/// ## Panics
+/// Panics if the slices differ in length.
+pub fn rmse(lhs: &[f32], rhs: &[f32]) -> f32 {
+    assert_eq!(lhs.len(), rhs.len(), "slice length mismatch");
+    let sum_sq = lhs
+        .iter()
+        .zip(rhs)
+        .fold(0.0, |sum, (&a, &b)| sum + (a - b) * (a - b));
+    (sum_sq / lhs.len() as f32).sqrt()
+}
+
+/// Replaces the scores with their softmax, i.e. a probability distribution summing to one.
+///
+/// The maximum score is subtracted before exponentiation, so large scores
+/// do not overflow.
+pub fn softmax_in_place(scores: &mut [f32]) {
+    let max = scores.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    if !max.is_finite() {
+        return;
+    }
+
+    let mut sum = 0.0;
+    for score in scores.iter_mut() {
+        *score = (*score - max).exp();
+        sum += *score;
+    }
+
+    let inv_sum = 1.0 / sum;
+    for score in scores.iter_mut() {
+        *score *= inv_sum;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temperature_works() {
+        let mut scores = [1.0, -2.0, 4.0];
+        apply_temperature(&mut scores, 2.0);
+        assert_eq!(scores, [0.5, -1.0, 2.0]);
+    }
+
+    #[test]
+    fn rmse_works() {
+        assert_eq!(rmse(&[1.0, 2.0], &[1.0, 2.0]), 0.0);
+        assert_eq!(rmse(&[0.0, 0.0], &[3.0, -3.0]), 3.0);
+    }
+
+    #[test]
+    fn softmax_sums_to_one() {
+        let mut scores = [1.0, 2.0, 3.0, 4.0];
+        softmax_in_place(&mut scores);
+        let sum: f32 = scores.iter().sum();
+        assert!((sum - 1.0).abs() < 1e-6);
+        assert!(scores.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn softmax_handles_large_scores() {
+        let mut scores = [1000.0, 1000.0, 990.0];
+        softmax_in_place(&mut scores);
+        assert!(scores.iter().all(|s| s.is_finite()));
+        let sum: f32 = scores.iter().sum();
+        assert!((sum - 1.0).abs() < 1e-6);
+        assert!((scores[0] - scores[1]).abs() < 1e-6);
+    }
+}
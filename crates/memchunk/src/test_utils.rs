@@ -0,0 +1,16 @@
+pub use crate::scores::rmse;
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro128Plus;
+
+/// Generates a query vector and a row-major data matrix of random values
+/// in the range `-1.0..1.0` from a fixed seed.
+pub fn generate_test_vectors(num_dims: usize, num_vecs: usize) -> (Vec<f32>, Vec<f32>) {
+    let mut rng = Xoshiro128Plus::seed_from_u64(1337);
+
+    let query = (0..num_dims).map(|_| rng.gen_range(-1.0..1.0)).collect();
+    let data = (0..num_dims * num_vecs)
+        .map(|_| rng.gen_range(-1.0..1.0))
+        .collect();
+
+    (query, data)
+}
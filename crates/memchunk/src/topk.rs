@@ -1,9 +1,17 @@
 #![allow(dead_code)]
 
-use std::cmp::Ordering;
-
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+/// Selects the `K` largest values.
+///
+/// NaN values are treated as smaller than any other value, i.e. they are only
+/// selected if fewer than `K` other values exist.
+/// The resulting values are not guaranteed to be sorted; see [`topk_sorted`].
+/// If fewer than `K` values are provided, the remaining entries are padded
+/// with an index of [`usize::MAX`] and a value of [`TopKValue::LOWEST`].
 #[inline(always)]
-pub fn topk<const K: usize>(values: &mut [f32]) -> [Entry; K] {
+pub fn topk<const K: usize, T: TopKValue>(values: &mut [T]) -> [EntryT<T>; K] {
     // NaiveBubble::topk::<K>(values)
     // NaiveUnstable::topk::<K>(values)
     QuickSelect::topk::<K>(values)
@@ -11,32 +19,214 @@ pub fn topk<const K: usize>(values: &mut [f32]) -> [Entry; K] {
     // MinHeap::topk::<K>(values)
 }
 
-pub trait TopK {
-    fn topk<const K: usize>(values: &mut [f32]) -> [Entry; K];
+/// Selects the `K` largest values in descending order.
+///
+/// Entries with equal values are ordered by ascending index; NaN values are ordered last.
+#[inline(always)]
+pub fn topk_sorted<const K: usize, T: TopKValue>(values: &mut [T]) -> [EntryT<T>; K] {
+    QuickSelect::topk_sorted::<K>(values)
+}
+
+/// Selects the `K` smallest values.
+///
+/// NaN values are treated as larger than any other value, i.e. they are only
+/// selected if fewer than `K` other values exist.
+/// The resulting values are not guaranteed to be sorted.
+/// If fewer than `K` values are provided, the remaining entries are padded
+/// with an index of [`usize::MAX`] and a value of [`TopKValue::HIGHEST`].
+#[inline(always)]
+pub fn bottomk<const K: usize, T: TopKValue>(values: &mut [T]) -> [EntryT<T>; K] {
+    QuickSelect::bottomk::<K>(values)
+}
+
+/// Selects the `K` largest values, using a random sample of the values to skip
+/// most of them; see [`TopK::topk_approx`].
+#[inline(always)]
+pub fn topk_approx<const K: usize, T: TopKValue>(
+    values: &mut [T],
+    sample_rate: f32,
+    seed: u64,
+) -> [EntryT<T>; K] {
+    QuickSelect::topk_approx::<K>(values, sample_rate, seed)
+}
+
+/// Merges partial top-K results, e.g. of scans split across threads or devices,
+/// into the `K` largest entries overall.
+///
+/// The indexes stored in the partial results are preserved; padding entries with an
+/// index of [`usize::MAX`] are only selected if fewer than `K` other entries exist.
+/// The resulting values are not guaranteed to be sorted.
+pub fn merge_topk<const K: usize, T: TopKValue>(partials: &[[EntryT<T>; K]]) -> [EntryT<T>; K] {
+    let entries: Vec<EntryT<T>> = partials.iter().flatten().copied().collect();
+    let mut values: Vec<T> = entries.iter().map(|entry| entry.value).collect();
+    topk::<K, T>(&mut values).map(|entry| match entries.get(entry.index) {
+        Some(&merged) => merged,
+        None => entry,
+    })
+}
+
+/// A value type the top-K selection can operate on.
+pub trait TopKValue: PartialOrd + Copy {
+    /// The value used to pad the results of a top-K selection over fewer than `K` values.
+    const LOWEST: Self;
+    /// The value used to pad the results of a bottom-K selection over fewer than `K` values.
+    const HIGHEST: Self;
+}
+
+macro_rules! impl_topk_value {
+    ($($type:ty),+; $lowest:ident, $highest:ident) => {
+        $(
+            impl TopKValue for $type {
+                const LOWEST: Self = <$type>::$lowest;
+                const HIGHEST: Self = <$type>::$highest;
+            }
+        )+
+    };
+}
+
+impl_topk_value!(f32, f64; NEG_INFINITY, INFINITY);
+impl_topk_value!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize; MIN, MAX);
+
+/// A top-K selection algorithm.
+pub trait TopK<T: TopKValue> {
+    /// Selects the `K` largest values.
+    ///
+    /// NaN values are treated as smaller than any other value, i.e. they are only
+    /// selected if fewer than `K` other values exist.
+    /// If fewer than `K` values are provided, the remaining entries are padded
+    /// with an index of [`usize::MAX`] and a value of [`TopKValue::LOWEST`].
+    fn topk<const K: usize>(values: &mut [T]) -> [EntryT<T>; K];
+
+    /// Selects the `K` largest values in descending order.
+    ///
+    /// Entries with equal values are ordered by ascending index; NaN values are ordered last.
+    fn topk_sorted<const K: usize>(values: &mut [T]) -> [EntryT<T>; K] {
+        let mut results = Self::topk::<K>(values);
+        sort_descending(&mut results);
+        results
+    }
+
+    /// Selects the `K` largest values, using a random sample of the values to skip
+    /// most of them.
+    ///
+    /// A cutoff is estimated from `sample_rate * values.len()` randomly sampled values,
+    /// such that at least `K` values reach it with high probability. A single pass then collects
+    /// all values reaching the cutoff and selects the `K` largest of those using
+    /// [`TopK::topk`]. If fewer than `K` values reach the cutoff, the selection falls back
+    /// to [`TopK::topk`] over all values. Either way, the selected values are the exact
+    /// `K` largest ones (up to ties); the sample rate only trades the cost of sampling
+    /// against the probability of the fallback.
+    ///
+    /// The sample is drawn from a generator seeded with `seed`, so results are reproducible.
+    /// The values are only reordered if the fallback is taken.
+    fn topk_approx<const K: usize>(
+        values: &mut [T],
+        sample_rate: f32,
+        seed: u64,
+    ) -> [EntryT<T>; K] {
+        let num_samples = (values.len() as f32 * sample_rate.clamp(0.0, 1.0)).ceil() as usize;
+        if K == 0 || values.len() <= K || num_samples == 0 || num_samples >= values.len() {
+            return Self::topk::<K>(values);
+        }
+
+        let mut rng = SplitMix64(seed);
+        let mut sample: Vec<T> = (0..num_samples)
+            .map(|_| values[rng.next_index(values.len())])
+            .filter(|value| value.partial_cmp(value).is_some())
+            .collect();
+
+        // The expected number of sampled values among the K largest, plus a safety
+        // margin of three standard deviations, determines the rank of the cutoff.
+        let expected = (K * sample.len()) as f32 / values.len() as f32;
+        let rank = (expected + 3.0 * expected.sqrt()).ceil() as usize + 3;
+        if sample.len() < rank {
+            return Self::topk::<K>(values);
+        }
+
+        let (_, &mut cutoff, _) = sample
+            .select_nth_unstable_by(rank - 1, |a, b| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+
+        let mut candidates = Vec::with_capacity(4 * K);
+        let mut indexes = Vec::with_capacity(4 * K);
+        for (i, &value) in values.iter().enumerate() {
+            if value >= cutoff {
+                candidates.push(value);
+                indexes.push(i);
+            }
+        }
+
+        if candidates.len() < K {
+            return Self::topk::<K>(values);
+        }
+
+        Self::topk::<K>(&mut candidates).map(|entry| EntryT::new(indexes[entry.index], entry.value))
+    }
+
+    /// Selects the `K` smallest values.
+    ///
+    /// NaN values are treated as larger than any other value, i.e. they are only
+    /// selected if fewer than `K` other values exist.
+    /// The resulting values are not guaranteed to be sorted.
+    fn bottomk<const K: usize>(values: &mut [T]) -> [EntryT<T>; K] {
+        if values.len() <= K {
+            return select_all_padded(values, T::HIGHEST);
+        }
+
+        let mut indexes: Vec<_> = (0..values.len()).collect();
+        let _ = quickselect_min(values, &mut indexes, K);
+        merge_into(values, &indexes)
+    }
+}
+
+/// A SplitMix64 generator used for sampling in [`TopK::topk_approx`].
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns an index in `0..len`.
+    fn next_index(&mut self, len: usize) -> usize {
+        ((self.next_u64() as u128 * len as u128) >> 64) as usize
+    }
+}
+
+/// Sorts the entries by descending value, breaking ties by ascending index.
+/// NaN values are ordered last.
+fn sort_descending<T: PartialOrd>(entries: &mut [EntryT<T>]) {
+    entries.sort_unstable_by(|a, b| cmp_nan_first(&b.value, &a.value).then(a.index.cmp(&b.index)));
 }
 
 struct NaiveBubble {}
-impl TopK for NaiveBubble {
-    fn topk<const K: usize>(values: &mut [f32]) -> [Entry; K] {
-        debug_assert_ne!(values.len(), 0);
+impl<T: TopKValue> TopK<T> for NaiveBubble {
+    fn topk<const K: usize>(values: &mut [T]) -> [EntryT<T>; K] {
+        if values.len() <= K {
+            return select_all_padded(values, T::LOWEST);
+        }
 
         // Initialize the results array.
-        let mut results = [Entry::from((0usize, -1.0f32)); K];
-        let last_idx = K - 1;
-
-        for i in 0..values.len() {
-            // Ignore all values that are smaller than the last entry in the list.
-            let v = values[i];
-            if v <= results[last_idx].value {
+        let mut results: Vec<EntryT<T>> = Vec::with_capacity(K);
+
+        for (i, &v) in values.iter().enumerate() {
+            if results.len() < K {
+                // Insert the value into a new last position.
+                results.push((i, v).into());
+            } else if cmp_nan_first(&v, &results[K - 1].value) != Ordering::Greater {
+                // Ignore all values that are smaller than the last entry in the list.
                 continue;
+            } else {
+                // Insert the value into the last position.
+                results[K - 1] = (i, v).into();
             }
 
-            // Insert the value into the last position.
-            results[last_idx] = (i, v).into();
-
             // Bubble up.
-            for j in (0..last_idx).rev() {
-                if v > results[j].value {
+            for j in (0..results.len() - 1).rev() {
+                if cmp_nan_first(&v, &results[j].value) == Ordering::Greater {
                     results.swap(j, j + 1);
                 } else {
                     break;
@@ -45,68 +235,81 @@ impl TopK for NaiveBubble {
         }
 
         results
+            .try_into()
+            .unwrap_or_else(|_| panic!("the vector is appropriately sized"))
     }
 }
 
 struct NaiveUnstable {}
-impl TopK for NaiveUnstable {
-    fn topk<const K: usize>(values: &mut [f32]) -> [Entry; K] {
-        debug_assert_ne!(values.len(), 0);
+impl<T: TopKValue> TopK<T> for NaiveUnstable {
+    fn topk<const K: usize>(values: &mut [T]) -> [EntryT<T>; K] {
+        if values.len() <= K {
+            return select_all_padded(values, T::LOWEST);
+        }
 
         // Initialize the results array.
-        let mut results = [Entry::from((0usize, 0.0f32)); K];
-        let mut min = f32::MAX;
-        for i in 0..K {
-            results[i] = (i, values[i]).into();
-            min = min.min(values[i]);
-        }
+        let mut results: [EntryT<T>; K] = std::array::from_fn(|i| (i, values[i]).into());
+        let mut min = smallest_value(&results);
 
         // Scan for values bigger than our current maximum.
-        for i in K..values.len() {
-            let v = values[i];
-            if v <= min {
+        for (i, &v) in values.iter().enumerate().skip(K) {
+            if cmp_nan_first(&v, &min) != Ordering::Greater {
                 continue;
             }
 
             // We found a value bigger than the smallest value we know.
             // Replace the smallest value with the new one.
-            let mut new_min = f32::MAX;
-            for j in 0..K {
-                if results[j].value == min {
-                    results[j] = (i, v).into();
-
-                    // Prevent condition from triggering again.
-                    min = f32::MAX;
-                }
-
-                // Determine the new smallest value.
-                new_min = new_min.min(results[j].value);
+            if let Some(entry) = results
+                .iter_mut()
+                .find(|entry| cmp_nan_first(&entry.value, &min) == Ordering::Equal)
+            {
+                *entry = (i, v).into();
             }
 
-            min = new_min;
+            // Determine the new smallest value.
+            min = smallest_value(&results);
         }
 
         results
     }
 }
 
+/// Determines the smallest value of a non-empty set of entries, treating NaN as smallest.
+fn smallest_value<T: PartialOrd + Copy>(entries: &[EntryT<T>]) -> T {
+    entries
+        .iter()
+        .map(|entry| entry.value)
+        .fold(entries[0].value, |min, v| {
+            if cmp_nan_first(&v, &min) == Ordering::Less {
+                v
+            } else {
+                min
+            }
+        })
+}
+
 struct QuickSelect {}
-impl TopK for QuickSelect {
-    fn topk<const K: usize>(values: &mut [f32]) -> [Entry; K] {
+impl<T: TopKValue> TopK<T> for QuickSelect {
+    fn topk<const K: usize>(values: &mut [T]) -> [EntryT<T>; K] {
+        if values.len() <= K {
+            return select_all_padded(values, T::LOWEST);
+        }
+
         let mut indexes: Vec<_> = (0..values.len()).collect();
         let _ = quickselect_max(values, &mut indexes, K);
-        merge_into(&values, &indexes)
+        merge_into(values, &indexes)
     }
 }
 
 struct QuickSelectIterative {}
-impl TopK for QuickSelectIterative {
-    fn topk<const K: usize>(values: &mut [f32]) -> [Entry; K] {
-        debug_assert!(values.len() >= K);
+impl<T: TopKValue> TopK<T> for QuickSelectIterative {
+    fn topk<const K: usize>(values: &mut [T]) -> [EntryT<T>; K] {
+        if values.len() <= K {
+            return select_all_padded(values, T::LOWEST);
+        }
 
         let buf_size = (2 * K).min(values.len());
-        let mut vs = vec![f32::NAN; buf_size];
-        vs.copy_from_slice(&values[0..buf_size]);
+        let mut vs = values[0..buf_size].to_vec();
         let mut is: Vec<_> = (0..buf_size).collect();
 
         let _ = quickselect_max(&mut vs, &mut is, K);
@@ -127,23 +330,53 @@ impl TopK for QuickSelectIterative {
     }
 }
 
-fn merge_into<const K: usize>(vs: &[f32], is: &[usize]) -> [Entry; K] {
+/// Selects all of no more than `K` values, padding the remaining entries
+/// with an index of [`usize::MAX`] and the specified value.
+fn select_all_padded<const K: usize, T: Copy>(values: &[T], padding: T) -> [EntryT<T>; K] {
+    debug_assert!(values.len() <= K);
+    std::array::from_fn(|i| match values.get(i) {
+        Some(&value) => EntryT::new(i, value),
+        None => EntryT::new(usize::MAX, padding),
+    })
+}
+
+fn merge_into<const K: usize, T: Copy>(vs: &[T], is: &[usize]) -> [EntryT<T>; K] {
     debug_assert_eq!(vs.len(), is.len());
-    let mut results = [Entry::new(0, 0f32); K];
-    for i in 0..K {
-        results[i] = Entry::new(is[i], vs[i]);
-    }
-    results
+    std::array::from_fn(|i| EntryT::new(is[i], vs[i]))
 }
 
-fn quickselect_max(data: &mut [f32], indexes: &mut [usize], k: usize) -> Entry {
+fn quickselect_max<T: PartialOrd + Copy>(
+    data: &mut [T],
+    indexes: &mut [usize],
+    k: usize,
+) -> EntryT<T> {
     let mut left = 0;
     let mut right = data.len() - 1;
 
     loop {
         let pivot_index = partition_max(data, indexes, left, right);
         if pivot_index == k {
-            return Entry::new(k, data[k]);
+            return EntryT::new(k, data[k]);
+        } else if k < pivot_index {
+            right = pivot_index - 1;
+        } else {
+            left = pivot_index + 1;
+        }
+    }
+}
+
+fn quickselect_min<T: PartialOrd + Copy>(
+    data: &mut [T],
+    indexes: &mut [usize],
+    k: usize,
+) -> EntryT<T> {
+    let mut left = 0;
+    let mut right = data.len() - 1;
+
+    loop {
+        let pivot_index = partition_min(data, indexes, left, right);
+        if pivot_index == k {
+            return EntryT::new(k, data[k]);
         } else if k < pivot_index {
             right = pivot_index - 1;
         } else {
@@ -152,6 +385,52 @@ fn quickselect_max(data: &mut [f32], indexes: &mut [usize], k: usize) -> Entry {
     }
 }
 
+fn partition_min<T: PartialOrd>(
+    data: &mut [T],
+    indexes: &mut [usize],
+    left: usize,
+    right: usize,
+) -> usize {
+    let pivot = right;
+    let mut i = left;
+
+    for j in left..right {
+        if is_le_nan_last(&data[j], &data[pivot]) {
+            data.swap(i, j);
+            indexes.swap(i, j);
+            i += 1;
+        }
+    }
+
+    data.swap(i, pivot);
+    indexes.swap(i, pivot);
+    i
+}
+
+/// Determines whether `a <= b`, treating NaN (i.e. values not comparable to themselves)
+/// as larger than any other value.
+#[inline(always)]
+fn is_le_nan_last<T: PartialOrd>(a: &T, b: &T) -> bool {
+    match a.partial_cmp(b) {
+        Some(ordering) => ordering != Ordering::Greater,
+        None => b.partial_cmp(b).is_none(),
+    }
+}
+
+/// Compares two values, treating NaN (i.e. values not comparable to themselves)
+/// as smaller than any other value.
+#[inline(always)]
+fn cmp_nan_first<T: PartialOrd>(a: &T, b: &T) -> Ordering {
+    match a.partial_cmp(b) {
+        Some(ordering) => ordering,
+        None => match (a.partial_cmp(a).is_none(), b.partial_cmp(b).is_none()) {
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            _ => Ordering::Equal,
+        },
+    }
+}
+
 fn partition_max<T: PartialOrd>(
     data: &mut [T],
     indexes: &mut [usize],
@@ -162,7 +441,7 @@ fn partition_max<T: PartialOrd>(
     let mut i = left;
 
     for j in left..right {
-        if data[j] >= data[pivot] {
+        if cmp_nan_first(&data[j], &data[pivot]) != Ordering::Less {
             data.swap(i, j);
             indexes.swap(i, j);
             i += 1;
@@ -175,81 +454,180 @@ fn partition_max<T: PartialOrd>(
 }
 
 pub struct MinHeap {}
-impl TopK for MinHeap {
-    fn topk<const K: usize>(values: &mut [f32]) -> [Entry; K] {
+impl<T: TopKValue> TopK<T> for MinHeap {
+    fn topk<const K: usize>(values: &mut [T]) -> [EntryT<T>; K] {
+        if values.len() <= K {
+            return select_all_padded(values, T::LOWEST);
+        }
+
         let mut heap = std::collections::BinaryHeap::new();
 
         // Insert the first K elements into the heap
-        for i in 0..K {
-            heap.push(std::cmp::Reverse(Entry::new(i, values[i])));
+        for (i, &v) in values.iter().enumerate().take(K) {
+            heap.push(std::cmp::Reverse(EntryT::new(i, v)));
         }
 
         // Insert the rest of the elements into the heap and pop off the smallest element
-        for i in K..values.len() {
-            heap.push(std::cmp::Reverse(Entry::new(i, values[i])));
+        for (i, &v) in values.iter().enumerate().skip(K) {
+            heap.push(std::cmp::Reverse(EntryT::new(i, v)));
             heap.pop();
         }
 
         // Extract the top K elements from the heap
-        let mut result = vec![Entry::new(0, 0.0); K];
-        for i in (0..K).rev() {
-            result[i] = heap.pop().unwrap().0;
+        let mut result = Vec::with_capacity(K);
+        while let Some(std::cmp::Reverse(entry)) = heap.pop() {
+            result.push(entry);
         }
+        result.reverse();
 
         result
+            .try_into()
+            .unwrap_or_else(|_| panic!("the vector is appropriately sized"))
+    }
+}
+
+/// Maintains a running selection of the `K` largest values pushed into it,
+/// e.g. when scores arrive in chunks and should not be buffered entirely.
+///
+/// NaN values are ignored.
+pub struct TopKAccumulator<const K: usize> {
+    heap: BinaryHeap<Reverse<Entry>>,
+    threshold: f32,
+}
+
+impl<const K: usize> TopKAccumulator<K> {
+    pub fn new() -> Self {
+        Self {
+            heap: BinaryHeap::with_capacity(K + 1),
+            threshold: f32::NEG_INFINITY,
+        }
+    }
+
+    /// The smallest value a new entry must exceed in order to be selected.
+    ///
+    /// This is [`f32::NEG_INFINITY`] until `K` values have been pushed.
+    pub fn threshold(&self) -> f32 {
+        self.threshold
+    }
+
+    /// Offers a value at the specified index to the selection.
+    #[inline(always)]
+    pub fn push(&mut self, index: usize, value: f32) {
+        if value.is_nan() {
+            return;
+        }
+
+        if self.heap.len() < K {
+            self.heap.push(Reverse(Entry::new(index, value)));
+            if self.heap.len() < K {
+                return;
+            }
+        } else if value > self.threshold {
+            self.heap.pop();
+            self.heap.push(Reverse(Entry::new(index, value)));
+        } else {
+            return;
+        }
+
+        self.threshold = self.heap.peek().map_or(f32::NEG_INFINITY, |e| e.0.value);
+    }
+
+    /// Offers a buffer of values to the selection, where the first value
+    /// is located at `base_index`.
+    pub fn extend(&mut self, values: &[f32], base_index: usize) {
+        for (i, &v) in values.iter().enumerate() {
+            self.push(base_index + i, v);
+        }
+    }
+
+    /// Returns the selected entries in descending order.
+    ///
+    /// If fewer than `K` values were selected, the remaining entries are
+    /// filled with `Entry::new(usize::MAX, f32::NEG_INFINITY)`.
+    pub fn finish(self) -> [Entry; K] {
+        let mut entries: Vec<Entry> = self.heap.into_iter().map(|e| e.0).collect();
+        sort_descending(&mut entries);
+        entries.resize(K, Entry::new(usize::MAX, f32::NEG_INFINITY));
+        entries
             .try_into()
             .expect("the vector is appropriately sized")
     }
 }
 
+impl<const K: usize> Default for TopKAccumulator<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A selected value and its index in the original buffer.
 #[derive(Debug, Copy, Clone)]
-pub struct Entry {
+pub struct EntryT<T> {
     index: usize,
-    value: f32,
+    value: T,
 }
 
-impl Entry {
-    pub fn new(index: usize, value: f32) -> Self {
+/// A selected [`f32`] value and its index in the original buffer.
+pub type Entry = EntryT<f32>;
+
+impl<T: Copy> EntryT<T> {
+    pub fn new(index: usize, value: T) -> Self {
         Self { index, value }
     }
+
+    /// The index of the value in the original buffer.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The value.
+    pub fn value(&self) -> T {
+        self.value
+    }
 }
 
-impl Into<(usize, f32)> for Entry {
-    fn into(self) -> (usize, f32) {
+impl<T> Into<(usize, T)> for EntryT<T> {
+    fn into(self) -> (usize, T) {
         (self.index, self.value)
     }
 }
 
-impl From<(usize, f32)> for Entry {
-    fn from(value: (usize, f32)) -> Self {
-        Self::new(value.0, value.1)
+impl<T> From<(usize, T)> for EntryT<T> {
+    fn from(value: (usize, T)) -> Self {
+        Self {
+            index: value.0,
+            value: value.1,
+        }
     }
 }
 
-impl PartialEq<Self> for Entry {
+impl<T: PartialEq> PartialEq<Self> for EntryT<T> {
     fn eq(&self, other: &Self) -> bool {
         self.value == other.value
     }
 }
 
-impl PartialOrd for Entry {
+impl<T: PartialOrd> PartialOrd for EntryT<T> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.value.partial_cmp(&other.value)
+        Some(self.cmp(other))
     }
 }
 
-impl Eq for Entry {}
-impl Ord for Entry {
+impl<T: PartialEq> Eq for EntryT<T> {}
+
+/// Entries are ordered by value, with NaN values ordered before all other values.
+impl<T: PartialOrd> Ord for EntryT<T> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.value
-            .partial_cmp(&other.value)
-            .unwrap_or(Ordering::Equal)
+        cmp_nan_first(&self.value, &other.value)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::topk::{quickselect_max, Entry, MinHeap, TopK};
+    use crate::topk::{
+        bottomk, merge_topk, quickselect_max, topk_approx, topk_sorted, Entry, EntryT, MinHeap,
+        NaiveBubble, NaiveUnstable, QuickSelect, QuickSelectIterative, TopK, TopKAccumulator,
+    };
 
     #[test]
     fn quickselect_works() {
@@ -269,4 +647,224 @@ mod tests {
         let k_largest = MinHeap::topk::<K>(&mut arr);
         assert_eq!(k_largest[K - 1], Entry::new(1, 3f32));
     }
+
+    #[test]
+    fn minheap_works_for_integers() {
+        let mut arr = [30i32, 3, 1, 12, 2, 11];
+        const K: usize = 2;
+        let k_largest = MinHeap::topk::<K>(&mut arr);
+        assert_eq!(k_largest[0], EntryT::new(0, 30));
+        assert_eq!(k_largest[1], EntryT::new(3, 12));
+    }
+
+    #[test]
+    fn implementations_agree() {
+        fn sorted_indexes<const K: usize>(entries: [Entry; K]) -> Vec<usize> {
+            let mut indexes: Vec<_> = entries.iter().map(|entry| entry.index()).collect();
+            indexes.sort();
+            indexes
+        }
+
+        let arr = [30f32, 3., 1., 12., 2., 11., 7., 19., 0.5, 4.];
+        let expected = vec![0, 3, 5, 7];
+
+        assert_eq!(
+            sorted_indexes(NaiveBubble::topk::<4>(&mut arr.clone())),
+            expected
+        );
+        assert_eq!(
+            sorted_indexes(NaiveUnstable::topk::<4>(&mut arr.clone())),
+            expected
+        );
+        assert_eq!(
+            sorted_indexes(QuickSelect::topk::<4>(&mut arr.clone())),
+            expected
+        );
+        assert_eq!(
+            sorted_indexes(QuickSelectIterative::topk::<4>(&mut arr.clone())),
+            expected
+        );
+        assert_eq!(
+            sorted_indexes(MinHeap::topk::<4>(&mut arr.clone())),
+            expected
+        );
+    }
+
+    #[test]
+    fn topk_approx_matches_exact() {
+        let mut values: Vec<f32> = (0..10_000).map(|i| ((i * 7919) % 10_007) as f32).collect();
+        let original = values.clone();
+        let mut exact = QuickSelect::topk::<10>(&mut values.clone());
+        let mut approx = topk_approx::<10, _>(&mut values, 0.05, 1337);
+
+        // The values are only reordered if the exact fallback was taken.
+        assert_eq!(values, original);
+
+        exact.sort_by_key(|entry| entry.index());
+        approx.sort_by_key(|entry| entry.index());
+        let pairs = |entries: [Entry; 10]| entries.map(|entry| (entry.index(), entry.value()));
+        assert_eq!(pairs(approx), pairs(exact));
+    }
+
+    #[test]
+    fn topk_approx_handles_plateaus() {
+        // The sample misses the single large value almost surely, but all other
+        // values reach the cutoff, so the result is still exact.
+        let mut values = vec![0.0f32; 1000];
+        values[123] = 1.0;
+        let best = topk_approx::<1, _>(&mut values, 0.01, 42);
+        assert_eq!(best[0], Entry::new(123, 1.0));
+
+        let mut values = [3.0f32, 1.0, 2.0];
+        let mut best = topk_approx::<2, _>(&mut values, 0.5, 0);
+        best.sort();
+        assert_eq!(best, [Entry::new(2, 2.0), Entry::new(0, 3.0)]);
+    }
+
+    #[test]
+    fn merge_topk_works() {
+        let partials = [
+            [Entry::new(3, 12.), Entry::new(1, 30.)],
+            [Entry::new(14, 11.), Entry::new(12, 19.)],
+            [
+                Entry::new(20, 7.),
+                Entry::new(usize::MAX, f32::NEG_INFINITY),
+            ],
+        ];
+        let mut merged = merge_topk(&partials);
+        merged.sort_by_key(|entry| entry.index());
+        let merged: Vec<(usize, f32)> = merged.iter().map(|&e| e.into()).collect();
+        assert_eq!(merged, [(1, 30.), (12, 19.)]);
+
+        let merged = merge_topk::<4, f32>(&[[
+            Entry::new(5, 1.),
+            Entry::new(usize::MAX, f32::NEG_INFINITY),
+            Entry::new(usize::MAX, f32::NEG_INFINITY),
+            Entry::new(usize::MAX, f32::NEG_INFINITY),
+        ]]);
+        assert_eq!(merged[0].index(), 5);
+        assert!(merged[1..].iter().all(|entry| entry.index() == usize::MAX));
+    }
+
+    #[test]
+    fn topk_sorted_works() {
+        let mut arr = [3f32, 30., 1., 12., 30., 11.];
+        let k_largest = topk_sorted::<4, _>(&mut arr);
+        let k_largest: Vec<(usize, f32)> = k_largest.iter().map(|&e| e.into()).collect();
+        assert_eq!(k_largest, [(1, 30.), (4, 30.), (3, 12.), (5, 11.)]);
+    }
+
+    #[test]
+    fn bottomk_works() {
+        let mut arr = [30f32, 3., f32::NAN, 12., 2., 11.];
+        let mut k_smallest = bottomk::<3, _>(&mut arr);
+        k_smallest.sort_by_key(|entry| entry.index());
+        let k_smallest: Vec<(usize, f32)> = k_smallest.iter().map(|&e| e.into()).collect();
+        assert_eq!(k_smallest, [(1, 3.), (4, 2.), (5, 11.)]);
+    }
+
+    #[test]
+    fn nan_values_are_not_selected() {
+        fn sorted_indexes<const K: usize>(entries: [Entry; K]) -> Vec<usize> {
+            let mut indexes: Vec<_> = entries.iter().map(|e| e.index()).collect();
+            indexes.sort();
+            indexes
+        }
+
+        let arr = [
+            f32::NAN,
+            3.,
+            30.,
+            f32::NAN,
+            1.,
+            12.,
+            f32::NAN,
+            11.,
+            f32::NAN,
+            f32::NAN,
+        ];
+        let expected = vec![2, 5, 7];
+
+        assert_eq!(
+            sorted_indexes(NaiveBubble::topk::<3>(&mut arr.clone())),
+            expected
+        );
+        assert_eq!(
+            sorted_indexes(NaiveUnstable::topk::<3>(&mut arr.clone())),
+            expected
+        );
+        assert_eq!(
+            sorted_indexes(QuickSelect::topk::<3>(&mut arr.clone())),
+            expected
+        );
+        assert_eq!(
+            sorted_indexes(QuickSelectIterative::topk::<3>(&mut arr.clone())),
+            expected
+        );
+        assert_eq!(
+            sorted_indexes(MinHeap::topk::<3>(&mut arr.clone())),
+            expected
+        );
+
+        let mut accumulator = TopKAccumulator::<3>::new();
+        accumulator.extend(&arr, 0);
+        assert_eq!(sorted_indexes(accumulator.finish()), expected);
+
+        // NaN values are only selected if there are not enough other values, and are ordered last.
+        let sorted = topk_sorted::<6, _>(&mut arr.clone());
+        let indexes: Vec<_> = sorted[..5].iter().map(|e| e.index()).collect();
+        assert_eq!(indexes, [2, 5, 7, 1, 4]);
+        assert!(sorted[5].value().is_nan());
+    }
+
+    #[test]
+    fn accumulator_works() {
+        let arr = [30f32, 3., 1., 12., 2., 11., 7., 19., 0.5, 4.];
+
+        let mut accumulator = TopKAccumulator::<3>::new();
+        accumulator.extend(&arr[..4], 0);
+        assert_eq!(accumulator.threshold(), 3.);
+        accumulator.extend(&arr[4..], 4);
+        assert_eq!(accumulator.threshold(), 12.);
+
+        let expected = topk_sorted::<3, _>(&mut arr.clone());
+        let actual = accumulator.finish();
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert_eq!(a.index(), e.index());
+            assert_eq!(a.value(), e.value());
+        }
+    }
+
+    #[test]
+    fn accumulator_pads_missing_entries() {
+        let mut accumulator = TopKAccumulator::<3>::new();
+        accumulator.push(7, 1.);
+        let entries = accumulator.finish();
+        assert_eq!(entries[0].index(), 7);
+        assert_eq!(entries[2].index(), usize::MAX);
+        assert_eq!(entries[2].value(), f32::NEG_INFINITY);
+    }
+
+    #[test]
+    fn fewer_values_than_k_are_padded() {
+        fn assert_padded(entries: [Entry; 10]) {
+            assert_eq!(entries[0], Entry::new(0, 42.));
+            assert_eq!(entries[0].index(), 0);
+            for entry in &entries[1..] {
+                assert_eq!(entry.index(), usize::MAX);
+                assert_eq!(entry.value(), f32::NEG_INFINITY);
+            }
+        }
+
+        let arr = [42f32];
+        assert_padded(NaiveBubble::topk::<10>(&mut arr.clone()));
+        assert_padded(NaiveUnstable::topk::<10>(&mut arr.clone()));
+        assert_padded(QuickSelect::topk::<10>(&mut arr.clone()));
+        assert_padded(QuickSelectIterative::topk::<10>(&mut arr.clone()));
+        assert_padded(MinHeap::topk::<10>(&mut arr.clone()));
+
+        let k_smallest = bottomk::<10, _>(&mut arr.clone());
+        assert_eq!(k_smallest[0].index(), 0);
+        assert_eq!(k_smallest[9].value(), f32::INFINITY);
+    }
 }
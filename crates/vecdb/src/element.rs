@@ -0,0 +1,160 @@
+use crate::header::ByteOrder;
+use std::fmt::{Display, Formatter};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// The numeric type of the values of a vector database file, as recorded in its header.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ElementType {
+    F32,
+    F64,
+    I8,
+    U8,
+}
+
+impl ElementType {
+    /// The tag of the element type in the header.
+    pub const fn to_u32(self) -> u32 {
+        match self {
+            ElementType::F32 => 0,
+            ElementType::F64 => 1,
+            ElementType::I8 => 2,
+            ElementType::U8 => 3,
+        }
+    }
+
+    /// Determines the element type from its tag in the header.
+    pub const fn from_u32(tag: u32) -> Option<Self> {
+        match tag {
+            0 => Some(ElementType::F32),
+            1 => Some(ElementType::F64),
+            2 => Some(ElementType::I8),
+            3 => Some(ElementType::U8),
+            _ => None,
+        }
+    }
+
+    /// The number of bytes of a value.
+    pub const fn size(self) -> usize {
+        match self {
+            ElementType::F32 => 4,
+            ElementType::F64 => 8,
+            ElementType::I8 | ElementType::U8 => 1,
+        }
+    }
+}
+
+impl Display for ElementType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ElementType::F32 => write!(f, "f32"),
+            ElementType::F64 => write!(f, "f64"),
+            ElementType::I8 => write!(f, "i8"),
+            ElementType::U8 => write!(f, "u8"),
+        }
+    }
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// A numeric type that can be stored in a [`TypedVecDb`](crate::TypedVecDb).
+///
+/// The trait is sealed, since values are reinterpreted from and to raw bytes.
+pub trait VecElement: private::Sealed + Copy + Default + Send + Sync + 'static {
+    /// The element type recorded in the header of files holding this type.
+    const ELEMENT_TYPE: ElementType;
+
+    /// The number of bytes of a value.
+    const SIZE: usize = Self::ELEMENT_TYPE.size();
+
+    /// Converts exactly [`VecElement::SIZE`] bytes in the specified byte order to a value.
+    fn from_bytes(bytes: &[u8], byte_order: ByteOrder) -> Self;
+
+    /// Appends the little-endian bytes of the value.
+    fn extend_le_bytes(self, bytes: &mut Vec<u8>);
+}
+
+macro_rules! impl_vec_element {
+    ($type:ty, $element_type:expr) => {
+        impl private::Sealed for $type {}
+
+        impl VecElement for $type {
+            const ELEMENT_TYPE: ElementType = $element_type;
+
+            #[inline(always)]
+            fn from_bytes(bytes: &[u8], byte_order: ByteOrder) -> Self {
+                let bytes = bytes.try_into().expect("value has an unexpected size");
+                match byte_order {
+                    ByteOrder::LittleEndian => <$type>::from_le_bytes(bytes),
+                    ByteOrder::BigEndian => <$type>::from_be_bytes(bytes),
+                }
+            }
+
+            #[inline(always)]
+            fn extend_le_bytes(self, bytes: &mut Vec<u8>) {
+                bytes.extend_from_slice(&self.to_le_bytes());
+            }
+        }
+    };
+}
+
+impl_vec_element!(f32, ElementType::F32);
+impl_vec_element!(f64, ElementType::F64);
+impl_vec_element!(i8, ElementType::I8);
+impl_vec_element!(u8, ElementType::U8);
+
+/// Reads a value in the specified byte order.
+#[inline(always)]
+pub(crate) async fn read_element<T: VecElement, R: AsyncRead + Unpin>(
+    reader: &mut R,
+    byte_order: ByteOrder,
+) -> Result<T, std::io::Error> {
+    let mut bytes = [0u8; 8];
+    let bytes = &mut bytes[..T::SIZE];
+    reader.read_exact(bytes).await?;
+    Ok(T::from_bytes(bytes, byte_order))
+}
+
+/// Writes the values in little-endian byte order.
+pub(crate) async fn write_elements_le<T: VecElement, W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    values: &[T],
+) -> Result<(), std::io::Error> {
+    if cfg!(target_endian = "little") {
+        // SAFETY: The bytes cover exactly the values, and u8 has no alignment requirement.
+        // All element types are plain numbers without padding.
+        let bytes = unsafe {
+            std::slice::from_raw_parts(values.as_ptr() as *const u8, std::mem::size_of_val(values))
+        };
+        return writer.write_all(bytes).await;
+    }
+
+    let mut bytes = Vec::with_capacity(std::mem::size_of_val(values));
+    for &value in values {
+        value.extend_le_bytes(&mut bytes);
+    }
+    writer.write_all(&bytes).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn element_type_tags_roundtrip() {
+        for element_type in [
+            ElementType::F32,
+            ElementType::F64,
+            ElementType::I8,
+            ElementType::U8,
+        ] {
+            assert_eq!(
+                ElementType::from_u32(element_type.to_u32()),
+                Some(element_type)
+            );
+        }
+        assert_eq!(ElementType::from_u32(4), None);
+        assert_eq!(<f64 as VecElement>::SIZE, 8);
+    }
+}
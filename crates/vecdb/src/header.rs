@@ -0,0 +1,250 @@
+use crate::element::ElementType;
+use abstractions::{NumDimensions, NumVectors};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// The byte order of the header fields and values of a vector database file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ByteOrder {
+    LittleEndian,
+    BigEndian,
+}
+
+impl ByteOrder {
+    fn u32_from_bytes(self, bytes: [u8; 4]) -> u32 {
+        match self {
+            ByteOrder::LittleEndian => u32::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u32::from_be_bytes(bytes),
+        }
+    }
+
+    fn u32_to_bytes(self, value: u32) -> [u8; 4] {
+        match self {
+            ByteOrder::LittleEndian => value.to_le_bytes(),
+            ByteOrder::BigEndian => value.to_be_bytes(),
+        }
+    }
+}
+
+/// The header of a vector database file.
+///
+/// | Length | Content                                                  |
+/// |--------|----------------------------------------------------------|
+/// | 4      | Version                                                  |
+/// | 4      | Byte order mark, or `u32::MAX` in the legacy version 0   |
+/// | 4      | Number of vectors                                        |
+/// | 4      | Number of dimensions                                     |
+///
+/// Files of version 2 store values other than `f32` and extend the header by
+///
+/// | Length | Content                                                  |
+/// |--------|----------------------------------------------------------|
+/// | 4      | Element type, see [`ElementType::to_u32`]                |
+/// | 12     | Reserved, zero                                           |
+///
+/// All fields are stored in the file's byte order, which is little-endian
+/// for the current versions and big-endian for the legacy version.
+#[derive(Debug, Copy, Clone)]
+pub struct VecDbHeader {
+    pub version: u32,
+    pub byte_order: ByteOrder,
+    pub num_vectors: NumVectors,
+    pub num_dimensions: NumDimensions,
+    /// The type of the values; always `f32` for versions before 2.
+    pub element_type: ElementType,
+}
+
+impl VecDbHeader {
+    /// The size of the header in bytes, excluding the extension of version 2.
+    pub const SIZE: usize = 16;
+
+    /// The size of the header of version 2 in bytes.
+    pub const EXTENDED_SIZE: usize = 32;
+
+    /// The current version of the file format for `f32` values.
+    pub const VERSION: u32 = 1;
+
+    /// The version of the file format recording the element type.
+    pub const ELEMENT_TYPE_VERSION: u32 = 2;
+
+    /// The version of the legacy big-endian file format without a byte order mark.
+    pub const LEGACY_VERSION: u32 = 0;
+
+    /// Stored in place of the legacy format's padding to identify the byte order.
+    pub const BYTE_ORDER_MARK: u32 = 0x0102_0304;
+
+    /// Creates a little-endian header of the current version.
+    pub fn new(num_vectors: NumVectors, num_dimensions: NumDimensions) -> Self {
+        Self {
+            version: Self::VERSION,
+            byte_order: ByteOrder::LittleEndian,
+            num_vectors,
+            num_dimensions,
+            element_type: ElementType::F32,
+        }
+    }
+
+    /// Sets the element type, using the version recording it for types other than `f32`.
+    pub fn with_element_type(mut self, element_type: ElementType) -> Self {
+        self.version = if element_type == ElementType::F32 {
+            Self::VERSION
+        } else {
+            Self::ELEMENT_TYPE_VERSION
+        };
+        self.element_type = element_type;
+        self
+    }
+
+    /// The size of the header in bytes, i.e. the offset of the first vector.
+    pub fn size(&self) -> usize {
+        if self.version >= Self::ELEMENT_TYPE_VERSION {
+            Self::EXTENDED_SIZE
+        } else {
+            Self::SIZE
+        }
+    }
+
+    /// Reads a header, detecting the file's byte order.
+    ///
+    /// Fails with [`std::io::ErrorKind::InvalidData`] if the byte order mark
+    /// or the version is not recognized.
+    pub async fn read_from<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self, std::io::Error> {
+        let mut header = [0u8; Self::SIZE];
+        reader.read_exact(&mut header).await?;
+
+        let field = |i: usize| -> [u8; 4] {
+            header[4 * i..4 * (i + 1)]
+                .try_into()
+                .expect("field has exactly four bytes")
+        };
+
+        let (byte_order, expected_versions) = match field(1) {
+            mark if mark == Self::BYTE_ORDER_MARK.to_le_bytes() => (
+                ByteOrder::LittleEndian,
+                Self::VERSION..=Self::ELEMENT_TYPE_VERSION,
+            ),
+            mark if mark == Self::BYTE_ORDER_MARK.to_be_bytes() => (
+                ByteOrder::BigEndian,
+                Self::VERSION..=Self::ELEMENT_TYPE_VERSION,
+            ),
+            padding if padding == u32::MAX.to_be_bytes() => (
+                ByteOrder::BigEndian,
+                Self::LEGACY_VERSION..=Self::LEGACY_VERSION,
+            ),
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "unrecognized vector database byte order mark",
+                ))
+            }
+        };
+
+        let version = byte_order.u32_from_bytes(field(0));
+        if !expected_versions.contains(&version) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("unsupported vector database version {version}"),
+            ));
+        }
+
+        let element_type = if version >= Self::ELEMENT_TYPE_VERSION {
+            let mut extension = [0u8; Self::EXTENDED_SIZE - Self::SIZE];
+            reader.read_exact(&mut extension).await?;
+            let tag = byte_order.u32_from_bytes(
+                extension[..4]
+                    .try_into()
+                    .expect("field has exactly four bytes"),
+            );
+            ElementType::from_u32(tag).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("unsupported vector database element type {tag}"),
+                )
+            })?
+        } else {
+            ElementType::F32
+        };
+
+        Ok(Self {
+            version,
+            byte_order,
+            num_vectors: (byte_order.u32_from_bytes(field(2)) as usize).into(),
+            num_dimensions: (byte_order.u32_from_bytes(field(3)) as usize).into(),
+            element_type,
+        })
+    }
+
+    /// Writes the header in its byte order.
+    ///
+    /// Headers of the legacy version are written with `u32::MAX` in place of the byte order mark.
+    pub async fn write_to<W: AsyncWrite + Unpin>(
+        &self,
+        writer: &mut W,
+    ) -> Result<(), std::io::Error> {
+        let mark = if self.version == Self::LEGACY_VERSION {
+            u32::MAX
+        } else {
+            Self::BYTE_ORDER_MARK
+        };
+
+        for field in [
+            self.version,
+            mark,
+            *self.num_vectors as u32,
+            *self.num_dimensions as u32,
+        ] {
+            writer
+                .write_all(&self.byte_order.u32_to_bytes(field))
+                .await?;
+        }
+
+        if self.version >= Self::ELEMENT_TYPE_VERSION {
+            writer
+                .write_all(&self.byte_order.u32_to_bytes(self.element_type.to_u32()))
+                .await?;
+            writer
+                .write_all(&[0u8; Self::EXTENDED_SIZE - Self::SIZE - 4])
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn header_roundtrip_works() {
+        for header in [
+            VecDbHeader::new(1000.into(), 128.into()),
+            VecDbHeader::new(5.into(), 64.into()).with_element_type(ElementType::I8),
+            VecDbHeader {
+                version: VecDbHeader::LEGACY_VERSION,
+                byte_order: ByteOrder::BigEndian,
+                num_vectors: 7.into(),
+                num_dimensions: 3.into(),
+                element_type: ElementType::F32,
+            },
+        ] {
+            let mut bytes = Vec::new();
+            header.write_to(&mut bytes).await.unwrap();
+            assert_eq!(bytes.len(), header.size());
+
+            let read = VecDbHeader::read_from(&mut bytes.as_slice()).await.unwrap();
+            assert_eq!(read.version, header.version);
+            assert_eq!(read.byte_order, header.byte_order);
+            assert_eq!(*read.num_vectors, *header.num_vectors);
+            assert_eq!(*read.num_dimensions, *header.num_dimensions);
+            assert_eq!(read.element_type, header.element_type);
+        }
+    }
+
+    #[tokio::test]
+    async fn unrecognized_headers_are_rejected() {
+        let bytes = [0u8; VecDbHeader::SIZE];
+        let error = VecDbHeader::read_from(&mut bytes.as_slice())
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,204 @@
+use crate::VecDb;
+use std::borrow::Borrow;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/// Error returned when importing vectors from a delimited text file.
+#[derive(Debug)]
+pub enum ImportError {
+    /// The input file could not be read.
+    Io(std::io::Error),
+    /// The vector database could not be written.
+    Mmap(fmmap::error::Error),
+    /// The input file contains no vectors.
+    Empty,
+    /// A field could not be parsed as a float.
+    InvalidValue {
+        line: usize,
+        column: usize,
+        value: String,
+    },
+    /// A row has a different number of fields than the first row.
+    DimensionMismatch {
+        line: usize,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl Display for ImportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportError::Io(e) => write!(f, "failed to read the input file: {e}"),
+            ImportError::Mmap(e) => write!(f, "failed to write the vector database: {e}"),
+            ImportError::Empty => write!(f, "the input file contains no vectors"),
+            ImportError::InvalidValue {
+                line,
+                column,
+                value,
+            } => write!(f, "line {line}, column {column}: invalid value {value:?}"),
+            ImportError::DimensionMismatch {
+                line,
+                expected,
+                actual,
+            } => write!(f, "line {line}: expected {expected} values, found {actual}"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImportError::Io(e) => Some(e),
+            ImportError::Mmap(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ImportError {
+    fn from(value: std::io::Error) -> Self {
+        ImportError::Io(value)
+    }
+}
+
+impl From<fmmap::error::Error> for ImportError {
+    fn from(value: fmmap::error::Error) -> Self {
+        ImportError::Mmap(value)
+    }
+}
+
+/// Imports vectors from a delimited text file into a new vector database.
+///
+/// Every non-empty row holds the values of one vector, separated by `delimiter`.
+/// The number of dimensions is taken from the first row; all other rows must match it.
+/// The input is read twice: once to count the vectors, once to write them.
+///
+/// ## Returns
+/// The opened vector database, positioned after the last written vector.
+pub async fn import_delimited<P: AsRef<Path>, B: Borrow<PathBuf>>(
+    input: P,
+    output: B,
+    delimiter: char,
+) -> Result<VecDb, ImportError> {
+    let (num_vectors, num_dimensions) = count_rows(input.as_ref(), delimiter).await?;
+    if num_vectors == 0 || num_dimensions == 0 {
+        return Err(ImportError::Empty);
+    }
+
+    let mut db = VecDb::open_write(output, num_vectors.into(), num_dimensions.into()).await?;
+
+    let mut lines = BufReader::new(File::open(input.as_ref()).await?).lines();
+    let mut line_number = 0;
+    let mut vec = Vec::with_capacity(num_dimensions);
+    while let Some(line) = lines.next_line().await? {
+        line_number += 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        parse_row(&line, delimiter, line_number, &mut vec)?;
+        if vec.len() != num_dimensions {
+            return Err(ImportError::DimensionMismatch {
+                line: line_number,
+                expected: num_dimensions,
+                actual: vec.len(),
+            });
+        }
+
+        db.write_vec(&vec).await?;
+    }
+
+    db.flush()?;
+    Ok(db)
+}
+
+/// Counts the non-empty rows and the number of fields in the first of them.
+async fn count_rows(input: &Path, delimiter: char) -> Result<(usize, usize), ImportError> {
+    let mut lines = BufReader::new(File::open(input).await?).lines();
+    let mut num_rows = 0;
+    let mut num_fields = 0;
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if num_rows == 0 {
+            num_fields = line.split(delimiter).count();
+        }
+        num_rows += 1;
+    }
+    Ok((num_rows, num_fields))
+}
+
+/// Parses the fields of a row into `vec`, replacing its previous contents.
+fn parse_row(
+    line: &str,
+    delimiter: char,
+    line_number: usize,
+    vec: &mut Vec<f32>,
+) -> Result<(), ImportError> {
+    vec.clear();
+    for (column, field) in line.split(delimiter).enumerate() {
+        let field = field.trim();
+        let value = field.parse().map_err(|_| ImportError::InvalidValue {
+            line: line_number,
+            column: column + 1,
+            value: field.to_string(),
+        })?;
+        vec.push(value);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn import_delimited_works() {
+        let input = std::env::temp_dir().join("vecdb_import_delimited.tsv");
+        let output = std::env::temp_dir().join("vecdb_import_delimited.bin");
+        std::fs::write(&input, "1\t2\t3\n\n4\t5\t6\n").unwrap();
+
+        let db = import_delimited(&input, &output, '\t').await.unwrap();
+        assert_eq!(*db.num_vectors, 2);
+        assert_eq!(*db.num_dimensions, 3);
+        drop(db);
+
+        let mut db = VecDb::open_read(&output).await.unwrap();
+        assert_eq!(db.read_vec().await.unwrap(), [1.0, 2.0, 3.0]);
+        assert_eq!(db.read_vec().await.unwrap(), [4.0, 5.0, 6.0]);
+
+        std::fs::remove_file(input).ok();
+        std::fs::remove_file(output).ok();
+    }
+
+    #[tokio::test]
+    async fn import_delimited_reports_line_numbers() {
+        let input = std::env::temp_dir().join("vecdb_import_delimited_errors.csv");
+        let output = std::env::temp_dir().join("vecdb_import_delimited_errors.bin");
+
+        std::fs::write(&input, "1,2\n3,x\n").unwrap();
+        match import_delimited(&input, &output, ',').await {
+            Err(ImportError::InvalidValue { line, column, .. }) => {
+                assert_eq!((line, column), (2, 2));
+            }
+            _ => panic!("expected an invalid value error"),
+        }
+
+        std::fs::write(&input, "1,2\n\n3,4,5\n").unwrap();
+        match import_delimited(&input, &output, ',').await {
+            Err(ImportError::DimensionMismatch {
+                line,
+                expected,
+                actual,
+            }) => assert_eq!((line, expected, actual), (3, 2, 3)),
+            _ => panic!("expected a dimension mismatch error"),
+        }
+
+        std::fs::remove_file(input).ok();
+        std::fs::remove_file(output).ok();
+    }
+}
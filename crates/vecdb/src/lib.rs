@@ -1,50 +1,96 @@
 use abstractions::{NumDimensions, NumVectors};
 use fmmap::tokio::{AsyncMmapFileExt, AsyncMmapFileMut, AsyncMmapFileMutExt, AsyncOptions};
+use memchunk::{AccessHint, AllocationHint, AnySizeMemoryChunk, ChunkError};
 use std::borrow::Borrow;
+use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
 use std::path::PathBuf;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 
-/// Vector Database File
-pub struct VecDb {
+mod element;
+mod header;
+mod import;
+mod reader;
+
+pub use element::{ElementType, VecElement};
+pub use header::{ByteOrder, VecDbHeader};
+pub use import::{import_delimited, ImportError};
+pub use reader::VecDbReader;
+
+use element::{read_element, write_elements_le};
+
+/// Vector Database File of `f32` vectors.
+pub type VecDb = TypedVecDb<f32>;
+
+/// Vector Database File of vectors with values of type `T`.
+///
+/// New files are written in little-endian byte order. Files of the legacy
+/// format version 0 are big-endian; the byte order is detected when opening a file.
+/// The element type is recorded in the header and verified when opening a file.
+///
+/// Dropping the database flushes it on a best-effort basis, ignoring errors, and does
+/// not guarantee durability. Call [`TypedVecDb::close`] after writing to observe failures.
+pub struct TypedVecDb<T: VecElement> {
     mmap: AsyncMmapFileMut,
     pub num_vectors: NumVectors,
     pub num_dimensions: NumDimensions,
+    byte_order: ByteOrder,
+    /// The size of the header, i.e. the offset of the first vector.
+    data_offset: usize,
     pos: usize,
+    /// Whether the file was written to since it was opened or explicitly flushed.
+    unclosed_writes: bool,
+    _element: PhantomData<T>,
 }
 
-impl VecDb {
-    const HEADER_SIZE: usize = 16;
-
+impl<T: VecElement> TypedVecDb<T> {
     pub async fn open_write<B: Borrow<PathBuf>>(
         path: B,
         num_vectors: NumVectors,
         num_dimensions: NumDimensions,
-    ) -> Result<VecDb, fmmap::error::Error> {
+    ) -> Result<Self, fmmap::error::Error> {
+        let header =
+            VecDbHeader::new(num_vectors, num_dimensions).with_element_type(T::ELEMENT_TYPE);
+        let file_size = num_vectors
+            .checked_bytes(num_dimensions, T::SIZE)
+            .and_then(|num_bytes| num_bytes.checked_add(header.size()))
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "vector database size overflows",
+                )
+            })?;
+
         let options = AsyncOptions::new()
             .read(true)
             .write(true)
             .create(true)
             .truncate(true)
-            .max_size((num_vectors * 4 * num_dimensions + Self::HEADER_SIZE) as u64)
-            .len(num_vectors * 4 * num_dimensions + Self::HEADER_SIZE);
+            .max_size(file_size as u64)
+            .len(file_size);
 
         let mut mmap = AsyncMmapFileMut::open_with_options(path.borrow(), options).await?;
         let mut writer = mmap.writer(0)?;
-        writer.write_u32(0).await?; // version
-        writer.write_u32(u32::MAX).await?; // padding
-        writer.write_u32(*num_vectors as u32).await?;
-        writer.write_u32(*num_dimensions as u32).await?;
+        header.write_to(&mut writer).await?;
         writer.flush().await?;
 
         Ok(Self {
             mmap,
             num_vectors,
             num_dimensions,
-            pos: Self::HEADER_SIZE,
+            byte_order: header.byte_order,
+            data_offset: header.size(),
+            pos: header.size(),
+            unclosed_writes: true,
+            _element: PhantomData,
         })
     }
 
-    pub async fn open_read<B: Borrow<PathBuf>>(path: B) -> Result<VecDb, fmmap::error::Error> {
+    /// Opens an existing file for reading, detecting its byte order from the header.
+    ///
+    /// Values are converted from the file's byte order when they are read.
+    /// Fails with [`std::io::ErrorKind::InvalidData`] if the file holds values of another type.
+    pub async fn open_read<B: Borrow<PathBuf>>(path: B) -> Result<Self, fmmap::error::Error> {
         let options = AsyncOptions::new()
             .read(true)
             .write(true)
@@ -53,32 +99,156 @@ impl VecDb {
 
         let mmap = AsyncMmapFileMut::open_with_options(path.borrow(), options).await?;
         let mut reader = mmap.reader(0)?;
-        let version = reader.read_u32().await?;
-        assert_eq!(version, 0, "Unsupported file version");
-        let _padding = reader.read_u32().await?;
-        let num_vectors = reader.read_u32().await?;
-        let num_dimensions = reader.read_u32().await?;
+        let header = VecDbHeader::read_from(&mut reader).await?;
+        if header.element_type != T::ELEMENT_TYPE {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "the vector database holds {} values, expected {}",
+                    header.element_type,
+                    T::ELEMENT_TYPE
+                ),
+            )
+            .into());
+        }
 
         Ok(Self {
             mmap,
-            num_vectors: num_vectors.into(),
-            num_dimensions: num_dimensions.into(),
-            pos: Self::HEADER_SIZE,
+            num_vectors: header.num_vectors,
+            num_dimensions: header.num_dimensions,
+            byte_order: header.byte_order,
+            data_offset: header.size(),
+            pos: header.size(),
+            unclosed_writes: false,
+            _element: PhantomData,
         })
     }
 
-    pub async fn write_vec<V: AsRef<[f32]>>(&mut self, vec: V) -> Result<(), std::io::Error> {
-        let vec = vec.as_ref();
-        assert_eq!(vec.len(), *self.num_dimensions);
-        let mut writer = self.mmap.writer(self.pos).unwrap(); // TODO: Fix
-        for float in vec {
-            writer.write_f32(*float).await?;
+    /// The byte order of the file.
+    pub fn byte_order(&self) -> ByteOrder {
+        self.byte_order
+    }
+
+    /// The index of the vector that is read or written next.
+    pub fn position(&self) -> usize {
+        (self.pos - self.data_offset)
+            .checked_div(self.vec_stride())
+            .unwrap_or(0)
+    }
+
+    /// The number of vectors following the current position.
+    pub fn remaining(&self) -> usize {
+        (*self.num_vectors).saturating_sub(self.position())
+    }
+
+    /// Moves to the vector at the specified index, so that it is read or written next.
+    ///
+    /// An index equal to the number of vectors moves to the end of the file;
+    /// larger indexes are rejected with [`std::io::ErrorKind::InvalidInput`].
+    pub fn seek_to(&mut self, index: usize) -> Result<(), std::io::Error> {
+        self.pos = self.offset_of(index)?;
+        Ok(())
+    }
+
+    /// Creates a reader with its own position, starting at the first vector.
+    ///
+    /// Readers only borrow the database immutably, so multiple readers can be used concurrently.
+    pub fn reader(&self) -> VecDbReader<'_, T> {
+        VecDbReader::new(self)
+    }
+
+    /// Determines the byte offset of the vector at the specified index,
+    /// which may be one past the last vector.
+    fn offset_of(&self, index: usize) -> Result<usize, std::io::Error> {
+        if index > *self.num_vectors {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "vector index {index} is out of bounds for {} vectors",
+                    *self.num_vectors
+                ),
+            ));
         }
-        self.pos += self.vec_stride();
+
+        index
+            .checked_mul(self.vec_stride())
+            .and_then(|offset| offset.checked_add(self.data_offset))
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("the offset of vector index {index} overflows"),
+                )
+            })
+    }
+
+    pub async fn write_vec<V: AsRef<[T]>>(&mut self, vec: V) -> Result<(), std::io::Error> {
+        self.write_matrix(vec.as_ref(), 1.into()).await
+    }
+
+    /// Writes multiple vectors at the current position using a single writer.
+    ///
+    /// Fails without writing anything if the vectors exceed the capacity of the file.
+    pub async fn write_vecs<V: AsRef<[T]>>(&mut self, vecs: &[V]) -> Result<(), std::io::Error> {
+        for vec in vecs {
+            assert_eq!(vec.as_ref().len(), *self.num_dimensions);
+        }
+
+        {
+            let mut writer = self.writer_for(vecs.len().into())?;
+            for vec in vecs {
+                write_elements_le(&mut writer, vec.as_ref()).await?;
+            }
+        }
+
+        self.pos += vecs.len() * self.vec_stride();
         Ok(())
     }
 
-    pub async fn read_vec_into<V: AsMut<[f32]>>(
+    /// Writes a contiguous row-major matrix of `num_vecs` vectors at the current position.
+    ///
+    /// Fails without writing anything if the vectors exceed the capacity of the file.
+    pub async fn write_matrix(
+        &mut self,
+        data: &[T],
+        num_vecs: NumVectors,
+    ) -> Result<(), std::io::Error> {
+        assert_eq!(data.len(), *num_vecs * *self.num_dimensions);
+
+        {
+            let mut writer = self.writer_for(num_vecs)?;
+            write_elements_le(&mut writer, data).await?;
+        }
+
+        self.pos += *num_vecs * self.vec_stride();
+        Ok(())
+    }
+
+    /// Creates a writer at the current position after ensuring that
+    /// the specified number of vectors fit into the file.
+    fn writer_for(
+        &mut self,
+        num_vecs: NumVectors,
+    ) -> Result<impl AsyncWrite + Unpin + '_, std::io::Error> {
+        let fits = num_vecs
+            .checked_bytes(self.num_dimensions, T::SIZE)
+            .and_then(|num_bytes| num_bytes.checked_add(self.pos))
+            .map_or(false, |end| end <= self.mmap.len());
+        if !fits {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                CapacityExceededError {
+                    num_vectors: self.num_vectors,
+                },
+            ));
+        }
+
+        self.unclosed_writes = true;
+        self.mmap
+            .writer(self.pos)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+    }
+
+    pub async fn read_vec_into<V: AsMut<[T]>>(
         &mut self,
         mut vec: V,
     ) -> Result<(), fmmap::error::Error> {
@@ -86,52 +256,128 @@ impl VecDb {
         assert_eq!(vec.len(), *self.num_dimensions);
         let mut reader = self.mmap.reader(self.pos)?;
         for i in self.num_dimensions {
-            vec[i] = reader.read_f32().await?;
+            vec[i] = read_element(&mut reader, self.byte_order).await?;
         }
         self.pos += self.vec_stride();
         Ok(())
     }
 
-    pub async fn read_vec(&mut self) -> Result<Vec<f32>, fmmap::error::Error> {
+    pub async fn read_vec(&mut self) -> Result<Vec<T>, fmmap::error::Error> {
         let mut reader = self.mmap.reader(self.pos)?;
         let mut vec = Vec::with_capacity(*self.num_dimensions);
         for _ in self.num_dimensions {
-            vec.push(reader.read_f32().await?);
+            vec.push(read_element(&mut reader, self.byte_order).await?);
         }
         self.pos += self.vec_stride();
         Ok(vec)
     }
 
+    /// Borrows the values of all vectors directly from the memory map, without copying.
+    ///
+    /// This requires the file's byte order to match the host's, which is the case
+    /// for files of the current format versions on little-endian hosts. The values are
+    /// suitably aligned since the memory map is page-aligned and the header is 16 or 32 bytes long.
+    ///
+    /// Returns `None` if the byte orders differ, the size declared by the header overflows,
+    /// or the file is shorter than its header declares.
+    pub fn as_slice(&self) -> Option<&[T]> {
+        let native = if cfg!(target_endian = "little") {
+            ByteOrder::LittleEndian
+        } else {
+            ByteOrder::BigEndian
+        };
+        if self.byte_order != native {
+            return None;
+        }
+
+        let num_values = self.num_vectors.checked_mul(self.num_dimensions)?;
+        let num_bytes = num_values.checked_mul(T::SIZE)?;
+        let end = self.data_offset.checked_add(num_bytes)?;
+        if end > self.mmap.len() {
+            return None;
+        }
+
+        let bytes = self.mmap.bytes(self.data_offset, num_bytes).ok()?;
+        debug_assert_eq!(bytes.len(), num_bytes);
+        if bytes.as_ptr().align_offset(std::mem::align_of::<T>()) != 0 {
+            return None;
+        }
+
+        // SAFETY: The bytes are aligned for T, span exactly `num_values` values
+        // in the host's byte order, and every bit pattern is a valid value of the element types.
+        Some(unsafe { std::slice::from_raw_parts(bytes.as_ptr() as *const T, num_values) })
+    }
+
+    /// Borrows the values of the vector at the specified index directly from the memory map.
+    ///
+    /// Returns `None` if the index is out of bounds or [`TypedVecDb::as_slice`] is not available.
+    pub fn vec_slice(&self, index: usize) -> Option<&[T]> {
+        if index >= *self.num_vectors {
+            return None;
+        }
+
+        let num_dims = *self.num_dimensions;
+        self.as_slice()
+            .map(|values| &values[index * num_dims..(index + 1) * num_dims])
+    }
+
+    /// Reads the next vector, stopping at the end of the file instead of failing
+    /// if the file is shorter than its header declares.
+    ///
+    /// Returns the values that could be read and whether the vector is complete.
+    /// Once the end of the file is reached, an empty, incomplete vector is returned.
+    pub fn read_vec_lossy(&mut self) -> Result<(Vec<T>, bool), fmmap::error::Error> {
+        let available = self.mmap.len().saturating_sub(self.pos) / T::SIZE;
+        let num_values = available.min(*self.num_dimensions);
+        if num_values == 0 {
+            return Ok((Vec::new(), *self.num_dimensions == 0));
+        }
+
+        let bytes = self.mmap.bytes(self.pos, num_values * T::SIZE)?;
+        let vec: Vec<T> = bytes
+            .chunks_exact(T::SIZE)
+            .map(|bytes| T::from_bytes(bytes, self.byte_order))
+            .collect();
+
+        let complete = num_values == *self.num_dimensions;
+        self.pos += if complete {
+            self.vec_stride()
+        } else {
+            num_values * T::SIZE
+        };
+        Ok((vec, complete))
+    }
+
     /// Reads all vectors from the file.
     /// For each vector, executes the specified function, passing the vector.
     ///
     /// If the provided function returns `true`, the next vector will be processed.
     /// If `false` is returned or no more vectors are available,
     /// processing stops and the number of processed vectors will be returned.
-    pub async fn read_all_vecs<F: FnMut(usize, &[f32]) -> bool>(
+    pub async fn read_all_vecs<F: FnMut(usize, &[T]) -> bool>(
         &mut self,
         fun: F,
     ) -> Result<usize, fmmap::error::Error> {
         self.read_n_vecs(self.num_vectors, fun).await
     }
 
-    /// Reads all vectors from the file.
+    /// Reads up to `count` vectors following the current position.
     /// For each vector, executes the specified function, passing the vector.
     ///
     /// If the provided function returns `true`, the next vector will be processed.
     /// If `false` is returned or no more vectors are available,
     /// processing stops and the number of processed vectors will be returned.
-    pub async fn read_n_vecs<F: FnMut(usize, &[f32]) -> bool>(
+    pub async fn read_n_vecs<F: FnMut(usize, &[T]) -> bool>(
         &mut self,
         count: NumVectors,
         mut fun: F,
     ) -> Result<usize, fmmap::error::Error> {
-        let count = self.num_vectors.min(*count);
+        let count = self.remaining().min(*count);
         let mut reader = self.mmap.reader(self.pos)?;
-        let mut vec = vec![0.0; *self.num_dimensions];
+        let mut vec = vec![T::default(); *self.num_dimensions];
         for v in 0..count {
             for i in self.num_dimensions {
-                vec[i] = reader.read_f32().await?;
+                vec[i] = read_element(&mut reader, self.byte_order).await?;
             }
             if !fun(v, &vec) {
                 return Ok(v + 1);
@@ -141,18 +387,623 @@ impl VecDb {
         Ok(count)
     }
 
+    /// Reads up to `count` vectors following the current position into the contiguous,
+    /// row-major `dest` buffer.
+    ///
+    /// The requested region of the file is split into `num_tasks` disjoint ranges
+    /// of vectors that are each converted from the on-disk byte order on a separate
+    /// thread, directly into the corresponding range of `dest`.
+    /// Returns the number of vectors read.
+    pub fn read_n_vecs_parallel(
+        &mut self,
+        count: NumVectors,
+        dest: &mut [T],
+        num_tasks: usize,
+    ) -> Result<usize, fmmap::error::Error> {
+        let count = self.remaining().min(*count);
+        let num_dims = *self.num_dimensions;
+        assert!(
+            count
+                .checked_mul(num_dims)
+                .map_or(false, |num_values| dest.len() >= num_values),
+            "destination buffer is too small"
+        );
+        if count == 0 {
+            return Ok(0);
+        }
+
+        let num_bytes = count.checked_mul(self.vec_stride()).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "the size of the requested vectors overflows",
+            )
+        })?;
+        let bytes = self.mmap.bytes(self.pos, num_bytes)?;
+        let byte_order = self.byte_order;
+
+        let vecs_per_task = (count + num_tasks.max(1) - 1) / num_tasks.max(1);
+        let values_per_task = vecs_per_task * num_dims;
+        std::thread::scope(|scope| {
+            for (dest, src) in dest[..count * num_dims]
+                .chunks_mut(values_per_task)
+                .zip(bytes.chunks(values_per_task * T::SIZE))
+            {
+                scope.spawn(move || {
+                    for (value, bytes) in dest.iter_mut().zip(src.chunks_exact(T::SIZE)) {
+                        *value = T::from_bytes(bytes, byte_order);
+                    }
+                });
+            }
+        });
+
+        self.pos += num_bytes;
+        Ok(count)
+    }
+
+    /// Writes the modified pages of the memory map back to the file.
+    ///
+    /// The file's data is durably stored once this returns successfully,
+    /// but its metadata (e.g. its size) and its directory entry may not be.
+    /// Use [`TypedVecDb::flush_sync`] if the file must survive a crash.
     pub fn flush(&mut self) -> Result<(), fmmap::error::Error> {
         self.mmap.flush()?;
+        self.unclosed_writes = false;
+        Ok(())
+    }
+
+    /// Writes the modified pages of the memory map back to the file and
+    /// synchronizes the file and its metadata with stable storage, like `fsync`.
+    ///
+    /// On Unix, the parent directory is synchronized as well, so that a newly
+    /// created file is found after a crash.
+    pub fn flush_sync(&mut self) -> Result<(), fmmap::error::Error> {
+        self.mmap.flush()?;
+
+        let path = self.mmap.path();
+        std::fs::File::open(path)?.sync_all()?;
+
+        #[cfg(unix)]
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::File::open(parent)?.sync_all()?;
+        }
+
+        self.unclosed_writes = false;
         Ok(())
     }
 
+    /// Synchronizes the file with stable storage using [`TypedVecDb::flush_sync`] and closes it.
+    ///
+    /// Unlike dropping the database, which flushes on a best-effort basis and
+    /// ignores errors, this surfaces a failed final flush to the caller.
+    pub fn close(mut self) -> Result<(), fmmap::error::Error> {
+        let result = self.flush_sync();
+        self.unclosed_writes = false;
+        result
+    }
+
     fn vec_stride(&self) -> usize {
-        4 * self.num_dimensions
+        T::SIZE * self.num_dimensions
+    }
+}
+
+impl VecDb {
+    /// Reads vectors into the chunk until either the chunk is full or no more vectors
+    /// are available, returning the number of vectors read.
+    ///
+    /// ## Panics
+    /// Panics if the chunk's number of dimensions differs from the file's.
+    pub async fn read_into_chunk(
+        &mut self,
+        chunk: &mut AnySizeMemoryChunk,
+    ) -> Result<usize, fmmap::error::Error> {
+        assert_eq!(
+            *chunk.num_dims(),
+            *self.num_dimensions,
+            "chunk dimension mismatch"
+        );
+
+        let num_dims = *self.num_dimensions;
+        let first = self.position();
+        let count = self.remaining().min(*chunk.num_vecs());
+        if let (true, Some(values)) = (num_dims > 0, self.as_slice()) {
+            let rows = values[first * num_dims..(first + count) * num_dims].chunks_exact(num_dims);
+            for (v, row) in rows.enumerate() {
+                chunk.get_vec_mut(v).copy_from_slice(row);
+            }
+            self.pos += count * self.vec_stride();
+            return Ok(count);
+        }
+
+        self.read_n_vecs(count.into(), |v, vec| {
+            chunk.get_vec_mut(v).copy_from_slice(vec);
+            true
+        })
+        .await
+    }
+
+    /// Allocates a chunk and reads up to `sample_size` of the remaining vectors into it.
+    ///
+    /// A `sample_size` of zero reads all remaining vectors.
+    /// Fails with [`LoadError::NoVectors`] if no vectors remain.
+    pub async fn load_chunk(
+        &mut self,
+        sample_size: NumVectors,
+        access_hint: AccessHint,
+    ) -> Result<AnySizeMemoryChunk, LoadError> {
+        let remaining = self.remaining();
+        if remaining == 0 {
+            return Err(LoadError::NoVectors);
+        }
+        let num_vecs = match *sample_size {
+            0 => remaining,
+            sample_size => sample_size.min(remaining),
+        };
+
+        let mut chunk = AnySizeMemoryChunk::try_new_with_hints(
+            num_vecs.into(),
+            self.num_dimensions,
+            access_hint,
+            AllocationHint::Default,
+        )?;
+
+        let num_read = self.read_into_chunk(&mut chunk).await?;
+        debug_assert_eq!(num_read, num_vecs);
+        Ok(chunk)
+    }
+}
+
+/// Error returned by [`VecDb::load_chunk`].
+#[derive(Debug)]
+pub enum LoadError {
+    /// The database is empty or all of its vectors were read already.
+    NoVectors,
+    /// The chunk could not be allocated.
+    Alloc(ChunkError),
+    /// The vectors could not be read.
+    Mmap(fmmap::error::Error),
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::NoVectors => write!(f, "no vectors remain to be read"),
+            LoadError::Alloc(e) => write!(f, "failed to allocate the chunk: {e}"),
+            LoadError::Mmap(e) => write!(f, "failed to read the vector database: {e}"),
+        }
     }
 }
 
-impl Drop for VecDb {
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::NoVectors => None,
+            LoadError::Alloc(e) => Some(e),
+            LoadError::Mmap(e) => Some(e),
+        }
+    }
+}
+
+impl From<ChunkError> for LoadError {
+    fn from(value: ChunkError) -> Self {
+        LoadError::Alloc(value)
+    }
+}
+
+impl From<fmmap::error::Error> for LoadError {
+    fn from(value: fmmap::error::Error) -> Self {
+        LoadError::Mmap(value)
+    }
+}
+
+/// Error indicating that more vectors were written than the file was sized for.
+///
+/// Returned by [`VecDb::write_vec`] wrapped in a [`std::io::Error`] of kind
+/// [`std::io::ErrorKind::InvalidInput`]; use [`std::io::Error::get_ref`] to inspect it.
+#[derive(Debug, Copy, Clone)]
+pub struct CapacityExceededError {
+    /// The number of vectors the file was created for.
+    pub num_vectors: NumVectors,
+}
+
+impl Display for CapacityExceededError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the vector database is full; it was created for {} vectors",
+            *self.num_vectors
+        )
+    }
+}
+
+impl std::error::Error for CapacityExceededError {}
+
+/// Flushes on a best-effort basis; see [`TypedVecDb::close`].
+impl<T: VecElement> Drop for TypedVecDb<T> {
     fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        if self.unclosed_writes {
+            eprintln!(
+                "Warning: vector database {path:?} was dropped with unflushed writes; use close() to observe errors",
+                path = self.mmap.path()
+            );
+        }
+
         self.flush().ok();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn read_n_vecs_parallel_works() {
+        let path = std::env::temp_dir().join("vecdb_read_n_vecs_parallel.bin");
+        let num_vecs = 7;
+        let num_dims = 16;
+
+        let mut db = VecDb::open_write(&path, num_vecs.into(), num_dims.into())
+            .await
+            .unwrap();
+        for v in 0..num_vecs {
+            let vec: Vec<f32> = (0..num_dims).map(|d| (v * num_dims + d) as f32).collect();
+            db.write_vec(vec).await.unwrap();
+        }
+        db.close().unwrap();
+
+        let mut db = VecDb::open_read(&path).await.unwrap();
+        let mut data = vec![0.0; num_vecs * num_dims];
+        let num_read = db
+            .read_n_vecs_parallel(num_vecs.into(), &mut data, 3)
+            .unwrap();
+
+        assert_eq!(num_read, num_vecs);
+        for (i, &value) in data.iter().enumerate() {
+            assert_eq!(value, i as f32);
+        }
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn bulk_writes_work() {
+        let path = std::env::temp_dir().join("vecdb_bulk_writes.bin");
+
+        let mut db = VecDb::open_write(&path, 5.into(), 2.into()).await.unwrap();
+        db.write_vecs(&[[0.0, 1.0], [2.0, 3.0]]).await.unwrap();
+        db.write_matrix(&[4.0, 5.0, 6.0, 7.0], 2.into())
+            .await
+            .unwrap();
+        assert_eq!(db.position(), 4);
+
+        let error = db
+            .write_matrix(&[8.0, 9.0, 10.0, 11.0], 2.into())
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(db.position(), 4);
+        db.write_vec([8.0, 9.0]).await.unwrap();
+        db.close().unwrap();
+
+        let mut db = VecDb::open_read(&path).await.unwrap();
+        for v in 0..5 {
+            let expected = [2.0 * v as f32, 2.0 * v as f32 + 1.0];
+            assert_eq!(db.read_vec().await.unwrap(), expected);
+        }
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn read_into_chunk_works() {
+        let path = std::env::temp_dir().join("vecdb_read_into_chunk.bin");
+
+        let mut db = VecDb::open_write(&path, 3.into(), 4.into()).await.unwrap();
+        for v in 0..3 {
+            db.write_vec([v as f32; 4]).await.unwrap();
+        }
+        db.close().unwrap();
+
+        let mut db = VecDb::open_read(&path).await.unwrap();
+        let mut chunk = AnySizeMemoryChunk::new(2.into(), 4.into());
+        assert_eq!(db.read_into_chunk(&mut chunk).await.unwrap(), 2);
+        assert_eq!(chunk.get_vec(1), [1.0; 4]);
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn load_chunk_clamps_sample_size() {
+        let path = std::env::temp_dir().join("vecdb_load_chunk.bin");
+
+        let mut db = VecDb::open_write(&path, 3.into(), 4.into()).await.unwrap();
+        for v in 0..3 {
+            db.write_vec([v as f32; 4]).await.unwrap();
+        }
+        db.close().unwrap();
+
+        let mut db = VecDb::open_read(&path).await.unwrap();
+        let chunk = db
+            .load_chunk(10.into(), AccessHint::Seqential)
+            .await
+            .unwrap();
+        assert_eq!(*chunk.num_vecs(), 3);
+        assert_eq!(chunk.get_vec(2), [2.0; 4]);
+
+        let mut db = VecDb::open_read(&path).await.unwrap();
+        db.read_vec().await.unwrap();
+        let chunk = db.load_chunk(0.into(), AccessHint::Random).await.unwrap();
+        assert_eq!(*chunk.num_vecs(), 2);
+        assert_eq!(chunk.get_vec(0), [1.0; 4]);
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn load_chunk_rejects_exhausted_files() {
+        let path = std::env::temp_dir().join("vecdb_load_chunk_exhausted.bin");
+
+        let db = VecDb::open_write(&path, 0.into(), 4.into()).await.unwrap();
+        db.close().unwrap();
+
+        let mut db = VecDb::open_read(&path).await.unwrap();
+        let error = db.load_chunk(0.into(), AccessHint::Random).await;
+        assert!(matches!(error, Err(LoadError::NoVectors)));
+
+        let mut db = VecDb::open_write(&path, 2.into(), 4.into()).await.unwrap();
+        db.write_vecs(&[[1.0; 4], [2.0; 4]]).await.unwrap();
+        db.close().unwrap();
+
+        let mut db = VecDb::open_read(&path).await.unwrap();
+        db.load_chunk(0.into(), AccessHint::Random).await.unwrap();
+        let error = db.load_chunk(1.into(), AccessHint::Random).await;
+        assert!(matches!(error, Err(LoadError::NoVectors)));
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn files_are_little_endian() {
+        let path = std::env::temp_dir().join("vecdb_little_endian.bin");
+
+        let mut db = VecDb::open_write(&path, 1.into(), 2.into()).await.unwrap();
+        db.write_vec([1.0, -2.5]).await.unwrap();
+        db.close().unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes[..4], 1u32.to_le_bytes());
+        assert_eq!(bytes[4..8], VecDbHeader::BYTE_ORDER_MARK.to_le_bytes());
+        assert_eq!(bytes[8..12], 1u32.to_le_bytes());
+        assert_eq!(bytes[12..16], 2u32.to_le_bytes());
+        assert_eq!(bytes[16..20], 1f32.to_le_bytes());
+        assert_eq!(bytes[20..24], (-2.5f32).to_le_bytes());
+
+        let mut db = VecDb::open_read(&path).await.unwrap();
+        assert_eq!(db.byte_order(), ByteOrder::LittleEndian);
+        assert_eq!(db.read_vec().await.unwrap(), [1.0, -2.5]);
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn legacy_big_endian_files_are_converted() {
+        let path = std::env::temp_dir().join("vecdb_legacy_big_endian.bin");
+
+        let words: [u32; 4] = [0, u32::MAX, 2, 2];
+        let values = [1f32, 2., 3., 4.];
+        let bytes: Vec<u8> = words
+            .iter()
+            .flat_map(|w| w.to_be_bytes())
+            .chain(values.iter().flat_map(|v| v.to_be_bytes()))
+            .collect();
+        std::fs::write(&path, bytes).unwrap();
+
+        let mut db = VecDb::open_read(&path).await.unwrap();
+        assert_eq!(db.byte_order(), ByteOrder::BigEndian);
+        assert_eq!((*db.num_vectors, *db.num_dimensions), (2, 2));
+        assert_eq!(db.read_vec().await.unwrap(), [1.0, 2.0]);
+
+        let mut db = VecDb::open_read(&path).await.unwrap();
+        let mut data = [0.0; 4];
+        db.read_n_vecs_parallel(2.into(), &mut data, 2).unwrap();
+        assert_eq!(data, values);
+
+        // Reads following the current position are clamped to the remaining vectors.
+        let mut db = VecDb::open_read(&path).await.unwrap();
+        db.seek_to(1).unwrap();
+        let mut data = [0.0; 4];
+        assert_eq!(db.read_n_vecs_parallel(2.into(), &mut data, 2).unwrap(), 1);
+        assert_eq!(data[..2], values[2..]);
+
+        db.seek_to(1).unwrap();
+        let mut chunk = AnySizeMemoryChunk::new(2.into(), 2.into());
+        assert_eq!(db.read_into_chunk(&mut chunk).await.unwrap(), 1);
+        assert_eq!(chunk.get_vec(0), &values[2..]);
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn read_vec_lossy_stops_at_truncation() {
+        let path = std::env::temp_dir().join("vecdb_read_vec_lossy.bin");
+
+        let mut db = VecDb::open_write(&path, 2.into(), 4.into()).await.unwrap();
+        db.write_vec([1.0; 4]).await.unwrap();
+        db.write_vec([2.0; 4]).await.unwrap();
+        db.close().unwrap();
+
+        let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len((VecDbHeader::SIZE + 6 * 4) as u64).unwrap();
+        drop(file);
+
+        let mut db = VecDb::open_read(&path).await.unwrap();
+        assert_eq!(db.read_vec_lossy().unwrap(), (vec![1.0; 4], true));
+        assert_eq!(db.read_vec_lossy().unwrap(), (vec![2.0; 2], false));
+        assert_eq!(db.read_vec_lossy().unwrap(), (vec![], false));
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn seek_to_works() {
+        let path = std::env::temp_dir().join("vecdb_seek_to.bin");
+
+        let mut db = VecDb::open_write(&path, 3.into(), 2.into()).await.unwrap();
+        for v in 0..3 {
+            db.write_vec([v as f32; 2]).await.unwrap();
+        }
+        assert_eq!((db.position(), db.remaining()), (3, 0));
+        db.close().unwrap();
+
+        let mut db = VecDb::open_read(&path).await.unwrap();
+        assert_eq!((db.position(), db.remaining()), (0, 3));
+
+        db.seek_to(2).unwrap();
+        assert_eq!(db.read_vec().await.unwrap(), [2.0; 2]);
+        assert_eq!((db.position(), db.remaining()), (3, 0));
+
+        db.seek_to(1).unwrap();
+        assert_eq!(db.read_vec().await.unwrap(), [1.0; 2]);
+
+        let error = db.seek_to(4).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(db.position(), 2);
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn readers_can_be_shared() {
+        let path = std::env::temp_dir().join("vecdb_readers.bin");
+
+        let mut db = VecDb::open_write(&path, 4.into(), 2.into()).await.unwrap();
+        for v in 0..4 {
+            db.write_vec([v as f32; 2]).await.unwrap();
+        }
+        db.close().unwrap();
+
+        let db = std::sync::Arc::new(VecDb::open_read(&path).await.unwrap());
+        let tasks: Vec<_> = (0..4)
+            .map(|v| {
+                let db = db.clone();
+                tokio::spawn(async move {
+                    let mut reader = db.reader();
+                    reader.seek_to(v).unwrap();
+                    reader.read_vec().await.unwrap()
+                })
+            })
+            .collect();
+
+        for (v, task) in tasks.into_iter().enumerate() {
+            assert_eq!(task.await.unwrap(), [v as f32; 2]);
+        }
+
+        let mut reader = db.reader();
+        let mut vec = [0.0; 2];
+        reader.read_vec_into(&mut vec).await.unwrap();
+        assert_eq!(vec, [0.0; 2]);
+        assert_eq!((reader.position(), reader.remaining()), (1, 3));
+
+        drop(db);
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn vec_slice_works() {
+        let path = std::env::temp_dir().join("vecdb_vec_slice.bin");
+
+        let mut db = VecDb::open_write(&path, 3.into(), 4.into()).await.unwrap();
+        for v in 0..3 {
+            db.write_vec([v as f32; 4]).await.unwrap();
+        }
+        db.close().unwrap();
+
+        let db = VecDb::open_read(&path).await.unwrap();
+        if cfg!(target_endian = "little") {
+            assert_eq!(db.as_slice().unwrap().len(), 12);
+            assert_eq!(db.vec_slice(2).unwrap(), [2.0; 4]);
+            assert!(db.vec_slice(3).is_none());
+        } else {
+            assert!(db.as_slice().is_none());
+        }
+
+        drop(db);
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn overflowing_headers_are_rejected() {
+        let path = std::env::temp_dir().join("vecdb_overflowing_header.bin");
+
+        // The declared size of 2^31 × 2^31 f32 values wraps to zero bytes.
+        let words: [u32; 4] = [1, VecDbHeader::BYTE_ORDER_MARK, 1 << 31, 1 << 31];
+        let bytes: Vec<u8> = words
+            .iter()
+            .flat_map(|w| w.to_le_bytes())
+            .chain(std::iter::repeat(0).take(64))
+            .collect();
+        std::fs::write(&path, bytes).unwrap();
+
+        let mut db = VecDb::open_read(&path).await.unwrap();
+        assert!(db.as_slice().is_none());
+        assert!(db.vec_slice(0).is_none());
+        assert!(db.seek_to(1 << 31).is_err());
+
+        drop(db);
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn write_vec_rejects_excess_vectors() {
+        let path = std::env::temp_dir().join("vecdb_write_vec_capacity.bin");
+
+        let mut db = VecDb::open_write(&path, 1.into(), 4.into()).await.unwrap();
+        db.write_vec([1.0; 4]).await.unwrap();
+
+        let error = db.write_vec([2.0; 4]).await.unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(error
+            .get_ref()
+            .map_or(false, |e| e.is::<CapacityExceededError>()));
+
+        db.close().unwrap();
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn element_types_are_recorded() {
+        let path = std::env::temp_dir().join("vecdb_element_types.bin");
+
+        let mut db = TypedVecDb::<f64>::open_write(&path, 2.into(), 3.into())
+            .await
+            .unwrap();
+        db.write_vecs(&[[1.0, -2.5, 1e300], [4.0, 5.0, 6.0]])
+            .await
+            .unwrap();
+        db.close().unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes.len(), VecDbHeader::EXTENDED_SIZE + 6 * 8);
+        assert_eq!(bytes[..4], VecDbHeader::ELEMENT_TYPE_VERSION.to_le_bytes());
+        assert_eq!(bytes[16..20], ElementType::F64.to_u32().to_le_bytes());
+
+        let mut db = TypedVecDb::<f64>::open_read(&path).await.unwrap();
+        assert_eq!(db.read_vec().await.unwrap(), [1.0, -2.5, 1e300]);
+        assert_eq!(db.vec_slice(1).unwrap(), [4.0, 5.0, 6.0]);
+        assert_eq!(db.reader().read_vec().await.unwrap(), [1.0, -2.5, 1e300]);
+
+        let error = VecDb::open_read(&path).await.err().unwrap();
+        assert!(error.to_string().contains("f64"));
+
+        let mut db = TypedVecDb::<i8>::open_write(&path, 1.into(), 4.into())
+            .await
+            .unwrap();
+        db.write_vec([-128, -1, 0, 127]).await.unwrap();
+        db.close().unwrap();
+
+        let mut db = TypedVecDb::<i8>::open_read(&path).await.unwrap();
+        assert_eq!(db.read_vec().await.unwrap(), [-128, -1, 0, 127]);
+
+        std::fs::remove_file(path).ok();
+    }
+}
@@ -0,0 +1,61 @@
+use crate::element::{read_element, VecElement};
+use crate::TypedVecDb;
+use fmmap::tokio::AsyncMmapFileExt;
+
+/// A read-only cursor over the vectors of a [`TypedVecDb`], created by [`TypedVecDb::reader`].
+///
+/// Each reader carries its own position, so any number of readers can read from
+/// the same memory map concurrently, e.g. from tasks sharing the database via an `Arc`.
+pub struct VecDbReader<'a, T: VecElement = f32> {
+    db: &'a TypedVecDb<T>,
+    pos: usize,
+}
+
+impl<'a, T: VecElement> VecDbReader<'a, T> {
+    pub(crate) fn new(db: &'a TypedVecDb<T>) -> Self {
+        Self {
+            db,
+            pos: db.data_offset,
+        }
+    }
+
+    /// The index of the vector that is read next.
+    pub fn position(&self) -> usize {
+        (self.pos - self.db.data_offset)
+            .checked_div(self.db.vec_stride())
+            .unwrap_or(0)
+    }
+
+    /// The number of vectors following the current position.
+    pub fn remaining(&self) -> usize {
+        (*self.db.num_vectors).saturating_sub(self.position())
+    }
+
+    /// Moves to the vector at the specified index, so that it is read next.
+    ///
+    /// See [`TypedVecDb::seek_to`].
+    pub fn seek_to(&mut self, index: usize) -> Result<(), std::io::Error> {
+        self.pos = self.db.offset_of(index)?;
+        Ok(())
+    }
+
+    pub async fn read_vec_into<V: AsMut<[T]>>(
+        &mut self,
+        mut vec: V,
+    ) -> Result<(), fmmap::error::Error> {
+        let vec = vec.as_mut();
+        assert_eq!(vec.len(), *self.db.num_dimensions);
+        let mut reader = self.db.mmap.reader(self.pos)?;
+        for value in vec.iter_mut() {
+            *value = read_element(&mut reader, self.db.byte_order).await?;
+        }
+        self.pos += self.db.vec_stride();
+        Ok(())
+    }
+
+    pub async fn read_vec(&mut self) -> Result<Vec<T>, fmmap::error::Error> {
+        let mut vec = vec![T::default(); *self.db.num_dimensions];
+        self.read_vec_into(&mut vec).await?;
+        Ok(vec)
+    }
+}